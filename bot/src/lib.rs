@@ -0,0 +1,2 @@
+pub mod strategy;
+pub mod view;
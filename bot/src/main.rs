@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use bot::strategy::strategy_by_name;
+use bot::view::BotView;
+use common::args::arg_value;
+use common::card::Faction;
+use common::deck::DeckChoice;
+use common::{ActionReq, InitReq, Response};
+use message_io::network::{Endpoint, NetEvent, Transport};
+use message_io::node::{self, NodeEvent, NodeHandler};
+
+// The server's framed TCP port rather than the clients' websocket one. message-io's
+// websocket adapter stops reading once the socket is drained, so frames tungstenite
+// has already buffered sit unread until more data arrives (`WsAdapter::receive` in
+// message-io 0.19). A bot only sends after it has heard back, so it would wait forever.
+const SERVER_ADDRESS: &str = "127.0.0.1:8081";
+// A single action comes back as several responses, give them all time to land
+// before deciding on the next move.
+const THINK_DELAY: Duration = Duration::from_millis(50);
+fn send(handler: &NodeHandler<()>, server: Endpoint, request: &ActionReq) {
+    let bytes = bincode::serialize(request).unwrap();
+    handler.network().send(server, &bytes);
+}
+
+/// `bot <name> <sanctum|thief> [table] [--strategy random|greedy] [--seed <n>] [--deck <name>]`
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let name = args.get(1).expect("Select name").clone();
+    let faction = match args.get(2).map(String::as_str) {
+        Some("sanctum") => Faction::Sanctum,
+        Some("thief") => Faction::Thief,
+        _ => panic!("Select thief or sanctum"),
+    };
+    let table = match args.get(3) {
+        Some(table) if !table.starts_with("--") => table.clone(),
+        _ => "default".to_string(),
+    };
+    let seed = arg_value(&args, "--seed").map_or_else(rand::random, |seed| {
+        seed.parse().expect("--seed must be a number")
+    });
+    let strategy_name = arg_value(&args, "--strategy").unwrap_or("greedy");
+    let mut strategy =
+        strategy_by_name(strategy_name, seed).expect("--strategy must be random or greedy");
+    let deck = match arg_value(&args, "--deck") {
+        Some(deck) => DeckChoice::Named(deck.to_string()),
+        None => DeckChoice::Named(format!("{}_starter", args[2])),
+    };
+
+    let (handler, listener) = node::split::<()>();
+    let (server, _) = handler
+        .network()
+        .connect(Transport::FramedTcp, SERVER_ADDRESS)
+        .unwrap();
+
+    let mut view: Option<BotView> = None;
+    // Sent and not answered yet.
+    let mut pending: Option<ActionReq> = None;
+    // Turned down by the server since the game last changed, not worth trying again.
+    let mut refused: Vec<ActionReq> = vec![];
+
+    listener.for_each(move |event| match event {
+        NodeEvent::Network(NetEvent::Connected(_, established)) => {
+            if !established {
+                println!("Couldn't reach the server at {}", SERVER_ADDRESS);
+                handler.stop();
+                return;
+            }
+            let join = InitReq {
+                table: table.clone(),
+                name: name.clone(),
+                faction,
+                deck: deck.clone(),
+            };
+            for request in join.join_requests() {
+                send(&handler, server, &request);
+            }
+            println!(
+                "{} playing {} at {} ({})",
+                name, faction, table, strategy_name
+            );
+        }
+        NodeEvent::Network(NetEvent::Accepted(_, _)) => (),
+        NodeEvent::Network(NetEvent::Message(_, data)) => {
+            let Ok(response) = bincode::deserialize::<Response>(data) else {
+                return;
+            };
+            match &response {
+                Response::Tables { .. }
+                | Response::LobbyRejected { .. }
+                | Response::Joined { .. } => {
+                    return;
+                }
+                Response::InitRejected { reason } => {
+                    println!("Server rejected us: {}", reason);
+                    handler.stop();
+                    return;
+                }
+                Response::ActionRejected { reason } => {
+                    println!("Move rejected: {}", reason);
+                    refused.extend(pending.take());
+                }
+                Response::Initial(state) => {
+                    view = Some(BotView::new(state));
+                    refused.clear();
+                }
+                _ => {
+                    if let Some(view) = view.as_mut() {
+                        view.apply(&response);
+                    }
+                    pending = None;
+                    refused.clear();
+                }
+            }
+            if let Some((winner, reason)) = view.as_ref().and_then(|view| view.game_over) {
+                println!("{} wins ({:?})", winner, reason);
+                handler.stop();
+                return;
+            }
+            handler.signals().send_with_timer((), THINK_DELAY);
+        }
+        NodeEvent::Network(NetEvent::Disconnected(_)) => {
+            println!("Lost the server");
+            handler.stop();
+        }
+        NodeEvent::Signal(()) => {
+            let Some(view) = view.as_ref() else {
+                return;
+            };
+            if pending.is_some() {
+                return;
+            }
+            let legal: Vec<ActionReq> = view
+                .legal_actions()
+                .into_iter()
+                .filter(|action| !refused.contains(action))
+                .collect();
+            if legal.is_empty() {
+                return;
+            }
+            let action = strategy.choose(view, &legal);
+            send(&handler, server, &action);
+            pending = Some(action);
+        }
+    });
+}
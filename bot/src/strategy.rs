@@ -0,0 +1,190 @@
+use common::card::{CardType, Counter, Effect, Faction};
+use common::{ActionReq, InstanceId, ServerTarget};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::view::BotView;
+
+/// Decides what a bot does whenever the game waits on it.
+pub trait Strategy {
+    /// Picks one of `legal`, which is never empty.
+    fn choose(&mut self, view: &BotView, legal: &[ActionReq]) -> ActionReq;
+}
+
+/// Reads `random` or `greedy`, seeding the random one with `seed`.
+pub fn strategy_by_name(name: &str, seed: u64) -> Option<Box<dyn Strategy>> {
+    match name {
+        "random" => Some(Box::new(RandomStrategy::new(seed))),
+        "greedy" => Some(Box::new(GreedyStrategy)),
+        _ => None,
+    }
+}
+
+/// Any legal move, uniformly.
+pub struct RandomStrategy {
+    rng: ChaCha8Rng,
+}
+impl RandomStrategy {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+impl Strategy for RandomStrategy {
+    fn choose(&mut self, _view: &BotView, legal: &[ActionReq]) -> ActionReq {
+        legal[self.rng.random_range(0..legal.len())].clone()
+    }
+}
+
+/// Takes the move that looks best right now, without looking ahead. Ties go to
+/// the first legal move.
+pub struct GreedyStrategy;
+impl GreedyStrategy {
+    fn score(view: &BotView, action: &ActionReq) -> i32 {
+        match (view.faction, action) {
+            (Faction::Sanctum, ActionReq::ScoreArtifact(_)) => 20,
+            (Faction::Sanctum, ActionReq::Attune(_)) => 9,
+            // A ward only stops the Thief once it's rezzed.
+            (Faction::Sanctum, ActionReq::Rez(_)) if view.infiltration.is_some() => 12,
+            // Get through the ward rather than let it fire.
+            (Faction::Thief, ActionReq::BreakSubroutines { .. }) => 12,
+            (Faction::Thief, ActionReq::PumpStrength(_)) => 11,
+            // Once in, see it through.
+            (_, ActionReq::Continue) => 10,
+            (_, ActionReq::JackOut) => 0,
+            // The Thief needs cards in hand to survive Backlash.
+            (Faction::Thief, ActionReq::DrawCard) if view.hand.len() < 2 => 8,
+            (Faction::Thief, ActionReq::Infiltrate(ServerTarget::HandLair))
+                if view.other_hand_size > 0 =>
+            {
+                6
+            }
+            (Faction::Thief, ActionReq::Infiltrate(ServerTarget::DeckLair))
+                if view.other_deck_size > 0 =>
+            {
+                5
+            }
+            (Faction::Thief, ActionReq::Infiltrate(ServerTarget::Remote(index)))
+                if view.servers[index + 3].contents.is_some() =>
+            {
+                7
+            }
+            (Faction::Thief, ActionReq::Infiltrate(_)) => 1,
+            (Faction::Thief, ActionReq::InstallRig(card)) => Self::rig_score(view, *card),
+            (_, ActionReq::PlayCard(card)) => Self::play_score(view, *card),
+            (Faction::Sanctum, ActionReq::Install { card, server }) => {
+                Self::install_score(view, *card, *server)
+            }
+            // Every draw brings the Sanctum closer to decking out.
+            (Faction::Sanctum, ActionReq::DrawCard) => 1,
+            (_, ActionReq::DrawCard) => 3,
+            (_, ActionReq::GainMana) => 2,
+            (_, ActionReq::SpendCounter { card, counter }) => {
+                Self::spend_score(view, *card, *counter)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Worth a click when it gives back more than a plain mana or draw would.
+    fn play_score(view: &BotView, card: InstanceId) -> i32 {
+        let Some(card) = view
+            .hand
+            .iter()
+            .find(|in_hand| in_hand.get_instance_id() == card)
+            .and_then(|in_hand| in_hand.get_card_id())
+            .map(|card_id| &view.card_set[&card_id])
+        else {
+            return 0;
+        };
+        let cost = card.data.cost().unwrap_or(0) as i32;
+        let mut score = 0;
+        for effect in card.effects.iter() {
+            score += match (view.faction, effect) {
+                (_, Effect::GainMana(amount)) if *amount as i32 - cost > 1 => 6,
+                (Faction::Thief, Effect::Draw(amount)) if *amount > 1 => 5,
+                _ => 0,
+            };
+        }
+        score
+    }
+
+    /// Spending a counter takes no stamina, so whatever the ability gives is
+    /// worth more than anything a click buys.
+    fn spend_score(view: &BotView, card: InstanceId, counter: Counter) -> i32 {
+        let Some(ability) = view.counter_ability(card, counter) else {
+            return 0;
+        };
+        let mut score = 0;
+        for effect in ability.effects.iter() {
+            score += match (view.faction, effect) {
+                (_, Effect::GainMana(amount)) => 12 + *amount as i32,
+                (Faction::Thief, Effect::Draw(amount)) => 12 + *amount as i32,
+                (Faction::Sanctum, Effect::DealDamage(amount)) => 12 + *amount as i32,
+                (Faction::Sanctum, Effect::EndInfiltration) if view.infiltration.is_some() => 15,
+                _ => 0,
+            };
+        }
+        score
+    }
+
+    /// One breaker per ward subtype is plenty.
+    fn rig_score(view: &BotView, card: InstanceId) -> i32 {
+        let Some(card_id) = view
+            .hand
+            .iter()
+            .find(|in_hand| in_hand.get_instance_id() == card)
+            .and_then(|in_hand| in_hand.get_card_id())
+        else {
+            return 0;
+        };
+        let CardType::CounterSpell { subtype, .. } = view.card_set[&card_id].data else {
+            return 4;
+        };
+        let covered = view.rig.iter().any(|installed| {
+            installed.get_card_id().is_some_and(|installed| {
+                matches!(
+                    view.card_set[&installed].data,
+                    CardType::CounterSpell { subtype: other, .. } if other == subtype
+                )
+            })
+        });
+        if covered { 0 } else { 9 }
+    }
+
+    /// Artifacts want a remote of their own, wards go where the artifacts are.
+    fn install_score(view: &BotView, card: InstanceId, server: ServerTarget) -> i32 {
+        let Some(card_id) = view
+            .hand
+            .iter()
+            .find(|in_hand| in_hand.get_instance_id() == card)
+            .and_then(|in_hand| in_hand.get_card_id())
+        else {
+            return 0;
+        };
+        let remote = match server {
+            ServerTarget::Remote(index) => view.servers.get(index + 3),
+            _ => None,
+        };
+        match (&view.card_set[&card_id].data, remote) {
+            (CardType::AncientArtifact { .. }, Some(remote)) if !remote.wards.is_empty() => 8,
+            (CardType::AncientArtifact { .. }, _) => 7,
+            (CardType::Asset { .. }, _) => 4,
+            (CardType::Ward { .. }, Some(remote)) if remote.contents.is_some() => 5,
+            (CardType::Ward { .. }, None) if server == ServerTarget::HandLair => 4,
+            _ => 0,
+        }
+    }
+}
+impl Strategy for GreedyStrategy {
+    fn choose(&mut self, view: &BotView, legal: &[ActionReq]) -> ActionReq {
+        let mut best = &legal[0];
+        for action in legal.iter().skip(1) {
+            if Self::score(view, action) > Self::score(view, best) {
+                best = action;
+            }
+        }
+        best.clone()
+    }
+}
@@ -0,0 +1,627 @@
+use std::collections::{HashMap, HashSet};
+
+use common::card::{CardData, CardState, CardType, Counter, CounterAbility, Faction};
+use common::rules::{ATTUNE_COST, FOCUS_CAPACITY, WARD_TAX};
+use common::{
+    ActionReq, BasicStats, CardId, GameOverReason, InfiltrationStep, InitStateResponse, InstanceId,
+    PlayerStateResponse, Response, ServerTarget,
+};
+
+/// One of the Sanctum's servers as this seat sees it. Face-down cards are only
+/// revealed to the Sanctum.
+#[derive(Debug, Clone)]
+pub struct ServerView {
+    pub target: ServerTarget,
+    pub wards: Vec<CardState>,
+    pub contents: Option<CardState>,
+}
+
+fn server_views(state: &PlayerStateResponse) -> Vec<ServerView> {
+    let PlayerStateResponse::Sanctum { specific, .. } = state else {
+        return vec![];
+    };
+    let lairs = [
+        (ServerTarget::HandLair, &specific.hand_lair),
+        (ServerTarget::DeckLair, &specific.deck_lair),
+        (ServerTarget::DiscardLair, &specific.discard_lair),
+    ];
+    let mut servers: Vec<ServerView> = lairs
+        .into_iter()
+        .map(|(target, wards)| ServerView {
+            target,
+            wards: wards.clone().unwrap_or_default(),
+            contents: None,
+        })
+        .collect();
+    for (index, remote) in specific.remotes.iter().flatten().enumerate() {
+        servers.push(ServerView {
+            target: ServerTarget::Remote(index),
+            wards: remote.wards.clone(),
+            contents: remote.contents.clone(),
+        });
+    }
+    servers
+}
+
+/// Everything a bot knows about its game, built only from what the server sent
+/// its seat. It never sees more than a human player in the same seat would.
+pub struct BotView {
+    pub faction: Faction,
+    pub card_set: HashMap<CardId, CardData>,
+    pub turn: Faction,
+    pub turn_number: u32,
+    pub my_stats: BasicStats,
+    pub other_stats: BasicStats,
+    pub hand: Vec<CardState>,
+    pub deck_size: u32,
+    pub other_hand_size: u32,
+    pub other_deck_size: u32,
+    /// The three lairs followed by the remotes, in `ServerTarget` order.
+    pub servers: Vec<ServerView>,
+    pub infiltration: Option<(ServerTarget, InfiltrationStep)>,
+    pub game_over: Option<(Faction, GameOverReason)>,
+    /// The Thief's counterspells, gear and allies, whichever side we're on.
+    pub rig: Vec<CardState>,
+    /// Strength each counterspell has been pumped to in the current encounter.
+    pub strengths: HashMap<InstanceId, u32>,
+    /// Subroutines of the encountered ward broken so far.
+    pub broken: Vec<usize>,
+    /// Our installed cards that are face-up. The Thief can tell from the cards themselves.
+    pub rezzed: HashSet<InstanceId>,
+}
+impl BotView {
+    pub fn new(state: &InitStateResponse) -> Self {
+        let mine = state.my_state.as_ref().unwrap();
+        let other_state = state.other_state.as_ref().unwrap();
+        let other = other_state.get_common();
+        let (sanctum, thief) = match mine.faction() {
+            Faction::Sanctum => (mine, other_state),
+            Faction::Thief => (other_state, mine),
+        };
+        let rig = match thief {
+            PlayerStateResponse::Thief { specific, .. } => [
+                &specific.spell_slots,
+                &specific.gear_slots,
+                &specific.ally_slots,
+            ]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .cloned()
+            .collect(),
+            PlayerStateResponse::Sanctum { .. } => vec![],
+        };
+        Self {
+            faction: mine.faction(),
+            card_set: state.card_set.clone(),
+            turn: state.turn,
+            turn_number: state.turn_number,
+            my_stats: mine.get_common().stats,
+            other_stats: other.stats,
+            hand: mine.get_common().hand.clone(),
+            deck_size: mine.get_common().deck_size,
+            other_hand_size: other.hand_size,
+            other_deck_size: other.deck_size,
+            servers: server_views(sanctum),
+            rig,
+            strengths: HashMap::new(),
+            broken: vec![],
+            infiltration: state.infiltration,
+            game_over: state.game_over,
+            rezzed: match mine {
+                PlayerStateResponse::Sanctum { specific, .. } => {
+                    specific.rezzed.iter().copied().collect()
+                }
+                PlayerStateResponse::Thief { .. } => HashSet::new(),
+            },
+        }
+    }
+
+    pub fn apply(&mut self, response: &Response) {
+        match response {
+            Response::Initial(state) => *self = Self::new(state),
+            Response::DrawCard { faction, card } => {
+                if *faction == self.faction {
+                    self.deck_size = self.deck_size.saturating_sub(1);
+                    self.hand.extend(card.clone());
+                } else {
+                    self.other_deck_size = self.other_deck_size.saturating_sub(1);
+                    self.other_hand_size += 1;
+                }
+            }
+            Response::StatsChanged { faction, stats } => {
+                if *faction == self.faction {
+                    self.my_stats = *stats;
+                } else {
+                    self.other_stats = *stats;
+                }
+            }
+            Response::TurnStarted { turn, turn_number } => {
+                self.turn = *turn;
+                self.turn_number = *turn_number;
+            }
+            Response::Infiltration { target, step } => {
+                self.infiltration = Some((*target, *step));
+                self.strengths.clear();
+                self.broken.clear();
+            }
+            Response::StrengthChanged { card, strength } => {
+                self.strengths.insert(*card, *strength);
+            }
+            Response::SubroutineBroken { subroutine, .. } => {
+                self.broken.push(*subroutine);
+            }
+            Response::InfiltrationEnded { .. } => {
+                self.infiltration = None;
+            }
+            Response::Discarded {
+                faction, from_hand, ..
+            } => self.leave_hand(*faction, *from_hand),
+            Response::Played {
+                faction, from_hand, ..
+            } => self.leave_hand(*faction, *from_hand),
+            Response::RigInstalled { card, from_hand } => {
+                self.leave_hand(Faction::Thief, *from_hand);
+                self.rig.push(card.clone());
+            }
+            Response::Installed {
+                card,
+                server,
+                ward,
+                from_hand,
+            } => {
+                self.leave_hand(Faction::Sanctum, *from_hand);
+                if let ServerTarget::Remote(index) = server
+                    && *index + 3 == self.servers.len()
+                {
+                    self.servers.push(ServerView {
+                        target: *server,
+                        wards: vec![],
+                        contents: None,
+                    });
+                }
+                let Some(installed_on) = self.server_mut(*server) else {
+                    return;
+                };
+                match ward {
+                    true => installed_on.wards.push(card.clone()),
+                    false => installed_on.contents = Some(card.clone()),
+                }
+            }
+            Response::Reveal { card } => {
+                self.rezzed.insert(card.get_instance_id());
+                for installed in self.installed_mut() {
+                    if installed.get_instance_id() == card.get_instance_id() {
+                        *installed = card.clone();
+                    }
+                }
+            }
+            Response::Scored { from, .. } => self.lose_card(*from),
+            Response::CountersChanged { card, counters } => {
+                for installed in self.installed_mut() {
+                    if installed.get_instance_id() == *card {
+                        *installed.counters_mut() = counters.clone();
+                    }
+                }
+            }
+            Response::GameOver { winner, reason } => {
+                self.infiltration = None;
+                self.game_over = Some((*winner, *reason));
+            }
+            _ => (),
+        }
+    }
+
+    /// `from_hand` is only known when it's our own hand.
+    fn leave_hand(&mut self, faction: Faction, from_hand: Option<InstanceId>) {
+        if faction == self.faction {
+            self.hand
+                .retain(|card| Some(card.get_instance_id()) != from_hand);
+        } else {
+            self.other_hand_size = self.other_hand_size.saturating_sub(1);
+        }
+    }
+
+    /// Every card on the table, the Sanctum's servers and the Thief's rig.
+    fn installed_mut(&mut self) -> impl Iterator<Item = &mut CardState> {
+        self.servers
+            .iter_mut()
+            .flat_map(|server| server.wards.iter_mut().chain(server.contents.as_mut()))
+            .chain(self.rig.iter_mut())
+    }
+
+    fn server_mut(&mut self, target: ServerTarget) -> Option<&mut ServerView> {
+        self.servers
+            .iter_mut()
+            .find(|server| server.target == target)
+    }
+
+    /// Ward position `position` of `target`, counted from the innermost one.
+    pub fn ward(&self, target: ServerTarget, position: usize) -> Option<&CardState> {
+        self.servers
+            .iter()
+            .find(|server| server.target == target)
+            .and_then(|server| server.wards.get(position))
+    }
+
+    fn is_fully_attuned(&self, card: &CardState) -> bool {
+        let Some(card_id) = card.get_card_id() else {
+            return false;
+        };
+        let CardType::AncientArtifact { attunement, .. } = self.card_set[&card_id].data else {
+            return false;
+        };
+        card.counters()
+            .get(&Counter::Attunement)
+            .copied()
+            .unwrap_or(0)
+            >= attunement
+    }
+
+    /// Takes a scored or stolen card off wherever we last saw it. A card
+    /// stolen from a hidden hand or off the deck was only ever counted.
+    fn lose_card(&mut self, card: InstanceId) {
+        for server in self.servers.iter_mut() {
+            if server
+                .contents
+                .as_ref()
+                .is_some_and(|contents| contents.get_instance_id() == card)
+            {
+                server.contents = None;
+                return;
+            }
+        }
+        let in_hand = self.hand.len();
+        self.hand
+            .retain(|in_hand| in_hand.get_instance_id() != card);
+        if self.hand.len() < in_hand {
+            return;
+        }
+        let (hand_size, deck_size) = match self.faction {
+            Faction::Sanctum => (None, &mut self.deck_size),
+            Faction::Thief => (Some(&mut self.other_hand_size), &mut self.other_deck_size),
+        };
+        match self.infiltration {
+            Some((ServerTarget::HandLair, _)) => {
+                if let Some(hand_size) = hand_size {
+                    *hand_size = hand_size.saturating_sub(1);
+                }
+            }
+            Some((ServerTarget::DeckLair, _)) => *deck_size = deck_size.saturating_sub(1),
+            _ => (),
+        }
+    }
+
+    /// Who the game is waiting on, mirroring the server's rule.
+    pub fn priority(&self) -> Faction {
+        match self.infiltration {
+            Some((_, InfiltrationStep::RezWindow(_))) => Faction::Sanctum,
+            Some(_) => Faction::Thief,
+            None => self.turn,
+        }
+    }
+
+    pub fn has_priority(&self) -> bool {
+        self.game_over.is_none() && self.priority() == self.faction
+    }
+
+    /// What the server would accept from us right now, as far as we can tell.
+    /// Empty while we don't have priority.
+    pub fn legal_actions(&self) -> Vec<ActionReq> {
+        if !self.has_priority() {
+            return vec![];
+        }
+        match self.infiltration {
+            Some((_, InfiltrationStep::ApproachWard(_) | InfiltrationStep::ApproachServer)) => {
+                vec![ActionReq::Continue, ActionReq::JackOut]
+            }
+            Some((target, InfiltrationStep::RezWindow(position))) => {
+                let mut actions = vec![ActionReq::Continue];
+                if let Some(ward) = self.ward(target, position)
+                    && self
+                        .card_cost(ward)
+                        .is_some_and(|cost| cost <= self.my_stats.mana_pool)
+                {
+                    actions.push(ActionReq::Rez(ward.get_instance_id()));
+                }
+                actions
+            }
+            Some((target, InfiltrationStep::EncounterWard(position))) => {
+                let mut actions = vec![ActionReq::Continue];
+                actions.extend(self.encounter_actions(target, position));
+                actions
+            }
+            Some(_) => vec![ActionReq::Continue],
+            None => {
+                if self.my_stats.stamina == 0 {
+                    return vec![];
+                }
+                let mut actions = vec![ActionReq::GainMana];
+                if self.deck_size > 0 {
+                    actions.push(ActionReq::DrawCard);
+                }
+                actions.extend(self.plays());
+                actions.extend(self.counter_spends());
+                match self.faction {
+                    Faction::Sanctum => {
+                        actions.extend(self.installs());
+                        actions.extend(self.asset_rezzes());
+                        actions.extend(self.artifact_actions());
+                    }
+                    Faction::Thief => {
+                        actions.extend(
+                            self.servers
+                                .iter()
+                                .map(|server| ActionReq::Infiltrate(server.target)),
+                        );
+                        actions.extend(self.rig_installs());
+                    }
+                }
+                actions
+            }
+        }
+    }
+
+    fn card_cost(&self, card: &CardState) -> Option<u32> {
+        let card_id = card.get_card_id()?;
+        self.card_set[&card_id].data.cost()
+    }
+
+    /// Counters on our own installed cards that do something when spent.
+    /// Our own cards on the board.
+    fn own_installed(&self) -> Vec<&CardState> {
+        match self.faction {
+            Faction::Sanctum => self
+                .servers
+                .iter()
+                .flat_map(|server| server.wards.iter().chain(server.contents.as_ref()))
+                .collect(),
+            Faction::Thief => self.rig.iter().collect(),
+        }
+    }
+
+    /// What spending `counter` on our installed `card` would do.
+    pub fn counter_ability(&self, card: InstanceId, counter: Counter) -> Option<&CounterAbility> {
+        let card_id = self
+            .own_installed()
+            .into_iter()
+            .find(|installed| installed.get_instance_id() == card)?
+            .get_card_id()?;
+        self.card_set[&card_id]
+            .counter_abilities
+            .iter()
+            .find(|ability| ability.counter == counter)
+    }
+
+    fn counter_spends(&self) -> Vec<ActionReq> {
+        let mut actions = vec![];
+        for card in self.own_installed() {
+            let Some(card_id) = card.get_card_id() else {
+                continue;
+            };
+            let data = &self.card_set[&card_id];
+            // The Thief's rig is always face-up, the Sanctum's cards need rezzing
+            // unless they're artifacts.
+            let usable = self.faction == Faction::Thief
+                || self.rezzed.contains(&card.get_instance_id())
+                || matches!(data.data, CardType::AncientArtifact { .. });
+            if !usable {
+                continue;
+            }
+            for (&counter, _) in card.counters().iter().filter(|(_, count)| **count > 0) {
+                let spendable = data
+                    .counter_abilities
+                    .iter()
+                    .any(|ability| ability.counter == counter);
+                if spendable {
+                    actions.push(ActionReq::SpendCounter {
+                        card: card.get_instance_id(),
+                        counter,
+                    });
+                }
+            }
+        }
+        actions
+    }
+
+    /// Events or Operations in hand we can afford.
+    fn plays(&self) -> Vec<ActionReq> {
+        self.hand
+            .iter()
+            .filter(|card| {
+                card.get_card_id().is_some_and(|card_id| {
+                    matches!(
+                        (self.faction, &self.card_set[&card_id].data),
+                        (Faction::Sanctum, CardType::Operation { .. })
+                            | (Faction::Thief, CardType::Event { .. })
+                    )
+                })
+            })
+            .filter(|card| self.card_cost(card).unwrap_or(0) <= self.my_stats.mana_pool)
+            .map(|card| ActionReq::PlayCard(card.get_instance_id()))
+            .collect()
+    }
+
+    /// Focus taken up by the counterspells in the rig.
+    pub fn focus_used(&self) -> u32 {
+        self.rig
+            .iter()
+            .filter_map(|card| card.get_card_id())
+            .map(|card_id| match self.card_set[&card_id].data {
+                CardType::CounterSpell { focus_cost, .. } => focus_cost,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Cards in hand we can afford to install, with room left for counterspells.
+    fn rig_installs(&self) -> Vec<ActionReq> {
+        let mut actions = vec![];
+        for card in self.hand.iter() {
+            let Some(card_id) = card.get_card_id() else {
+                continue;
+            };
+            let fits = match self.card_set[&card_id].data {
+                CardType::CounterSpell { focus_cost, .. } => {
+                    self.focus_used() + focus_cost <= FOCUS_CAPACITY
+                }
+                CardType::MagicalGear | CardType::Ally => true,
+                _ => false,
+            };
+            if fits && self.card_cost(card).unwrap_or(0) <= self.my_stats.mana_pool {
+                actions.push(ActionReq::InstallRig(card.get_instance_id()));
+            }
+        }
+        actions
+    }
+
+    /// Pumping a counterspell up to the encountered ward's strength, then
+    /// breaking as many subroutines as one payment allows.
+    fn encounter_actions(&self, target: ServerTarget, position: usize) -> Vec<ActionReq> {
+        let Some(ward_id) = self
+            .ward(target, position)
+            .and_then(|ward| ward.get_card_id())
+        else {
+            return vec![];
+        };
+        let CardType::Ward {
+            subtype: ward_subtype,
+            strength: ward_strength,
+            subroutines,
+            ..
+        } = &self.card_set[&ward_id].data
+        else {
+            return vec![];
+        };
+        let unbroken: Vec<usize> = (0..subroutines.len())
+            .filter(|subroutine| !self.broken.contains(subroutine))
+            .collect();
+        if unbroken.is_empty() {
+            return vec![];
+        }
+
+        let mut actions = vec![];
+        for card in self.rig.iter() {
+            let Some(card_id) = card.get_card_id() else {
+                continue;
+            };
+            let CardType::CounterSpell {
+                subtype,
+                strength,
+                pump,
+                break_subroutines,
+                ..
+            } = self.card_set[&card_id].data
+            else {
+                continue;
+            };
+            if subtype.breaks() != *ward_subtype {
+                continue;
+            }
+            let instance_id = card.get_instance_id();
+            let strength = self
+                .strengths
+                .get(&instance_id)
+                .copied()
+                .unwrap_or(strength);
+            if strength < *ward_strength {
+                if let Some(pump) = pump
+                    && pump.cost.0 <= self.my_stats.mana_pool
+                {
+                    actions.push(ActionReq::PumpStrength(instance_id));
+                }
+            } else if break_subroutines.amount > 0
+                && break_subroutines.cost.0 <= self.my_stats.mana_pool
+            {
+                actions.push(ActionReq::BreakSubroutines {
+                    counterspell: instance_id,
+                    subroutines: unbroken
+                        .iter()
+                        .copied()
+                        .take(break_subroutines.amount as usize)
+                        .collect(),
+                });
+            }
+        }
+        actions
+    }
+
+    /// Face-down assets in our remotes we can afford to flip.
+    fn asset_rezzes(&self) -> Vec<ActionReq> {
+        self.servers
+            .iter()
+            .filter_map(|server| server.contents.as_ref())
+            .filter(|contents| !self.rezzed.contains(&contents.get_instance_id()))
+            .filter(|contents| {
+                contents.get_card_id().is_some_and(|card_id| {
+                    matches!(self.card_set[&card_id].data, CardType::Asset { .. })
+                })
+            })
+            .filter(|contents| {
+                self.card_cost(contents)
+                    .is_some_and(|cost| cost <= self.my_stats.mana_pool)
+            })
+            .map(|contents| ActionReq::Rez(contents.get_instance_id()))
+            .collect()
+    }
+
+    /// Attuning or scoring whatever artifacts sit in our remotes.
+    fn artifact_actions(&self) -> Vec<ActionReq> {
+        let mut actions = vec![];
+        for server in self.servers.iter().skip(3) {
+            let Some(contents) = &server.contents else {
+                continue;
+            };
+            let Some(card_id) = contents.get_card_id() else {
+                continue;
+            };
+            if !matches!(
+                self.card_set[&card_id].data,
+                CardType::AncientArtifact { .. }
+            ) {
+                continue;
+            }
+            if self.is_fully_attuned(contents) {
+                actions.push(ActionReq::ScoreArtifact(contents.get_instance_id()));
+            } else if self.my_stats.mana_pool >= ATTUNE_COST {
+                actions.push(ActionReq::Attune(contents.get_instance_id()));
+            }
+        }
+        actions
+    }
+
+    /// Every server each card in hand could go on, plus a new remote.
+    fn installs(&self) -> Vec<ActionReq> {
+        let new_remote = ServerTarget::Remote(self.servers.len() - 3);
+        let mut actions = vec![];
+        for card in self.hand.iter() {
+            let Some(card_id) = card.get_card_id() else {
+                continue;
+            };
+            let install = |server| ActionReq::Install {
+                card: card.get_instance_id(),
+                server,
+            };
+            match self.card_set[&card_id].data {
+                CardType::Ward { .. } => {
+                    for server in self.servers.iter() {
+                        if server.wards.len() as u32 * WARD_TAX <= self.my_stats.mana_pool {
+                            actions.push(install(server.target));
+                        }
+                    }
+                    actions.push(install(new_remote));
+                }
+                CardType::Asset { .. } | CardType::AncientArtifact { .. } => {
+                    for server in self.servers.iter().skip(3) {
+                        if server.contents.is_none() {
+                            actions.push(install(server.target));
+                        }
+                    }
+                    actions.push(install(new_remote));
+                }
+                _ => (),
+            }
+        }
+        actions
+    }
+}
@@ -1,47 +1,24 @@
-use std::collections::HashMap;
-use common::{CardId, InstanceId, Response};
 use macroquad::prelude::*;
 use message_io::network::Endpoint;
 use message_io::node::NodeHandler;
-use common::ActionReq::DrawCard;
-use common::card::CardState;
+use common::{ActionReq, InstanceId};
+use common::card::{CardState, Counters};
 use crate::card_view::CardView;
-use crate::Net;
 
-struct Timer {
-    current: f64,
-}
-impl Timer {
-    fn now() -> Timer {
-        Timer {
-            current: get_time(),
-        }
-    }
-    fn elapsed(&self) -> u64 {
-        ((get_time() - self.current) * 1000.0) as u64
-    }
-}
 pub struct DragInfo {
     selected_card: usize,
-    from_position: Vec3,
     from_target_id: Option<usize>,
     drag_offset: Vec3,
 }
-pub struct FocusInfo {
-    selected_card: usize,
-    previous_scale: f32,
-}
 pub struct Board<'texture> {
     pub targets: Vec<DropTarget>,
     cards: Vec<CardView<'texture>>,
     current_drag: Option<DragInfo>,
-    current_focus: Option<FocusInfo>,
-    focus_timer: Timer,
+    // A card is zoomed in under the mouse.
+    has_focus: bool,
 }
 
 pub enum TargetType {
-    Event,
-    BoardV,
     BoardH,
     Trash,
     Hand,
@@ -54,6 +31,8 @@ pub const MY_TRASH: usize = 2;
 pub const OTHER_HAND: usize = 3;
 pub const OTHER_DECK: usize = 4;
 pub const OTHER_TRASH: usize = 5;
+pub const MY_BOARD: usize = 6;
+pub const OTHER_BOARD: usize = 7;
 
 pub struct DropTarget {
     pub id: usize,
@@ -74,24 +53,78 @@ impl<'texture> Board<'texture> {
             cards: vec![],
             targets,
             current_drag: None,
-            current_focus: None,
-            focus_timer: Timer::now(),
+            has_focus: false,
         }
     }
-    pub fn draw_card(&mut self, card_state: &CardState, textures: &'texture std::collections::HashMap<std::string::String, macroquad::texture::Texture2D>) {
-
-        let card_view  = self.cards.iter_mut().find(|t| t.card_state.get_instance_id() == card_state.get_instance_id()).unwrap();
-        match card_state {
-            CardState::Revealed(instance_id, card_id) => {
-                card_view.card_state = card_state.clone();
+    /// Moves a face-down card from a deck to the matching hand. We only get to
+    /// see the card when it's our own draw.
+    pub fn draw_card(&mut self, mine: bool, card_state: &Option<CardState>, textures: &'texture std::collections::HashMap<std::string::String, macroquad::texture::Texture2D>) {
+        let (deck, hand) = if mine { (MY_DECK, MY_HAND) } else { (OTHER_DECK, OTHER_HAND) };
+        let Some(card_view) = self.cards.iter_mut().find(|t| t.attached_to_target == Some(deck)) else {
+            return;
+        };
+        if let Some(card_state @ CardState::Revealed(_, card_id, _)) = card_state {
+            card_view.card_state = card_state.clone();
+            card_view.texture = &textures[card_id];
+        }
+        card_view.attached_to_target = Some(hand);
+        self.update_layout(deck);
+        self.update_layout(hand);
+    }
+    /// Takes a card out of a hand, the one with `from_hand`'s id when we know
+    /// it, otherwise any, since cards in a hidden hand are all alike to us.
+    pub fn remove_from_hand(&mut self, mine: bool, from_hand: Option<InstanceId>) {
+        let hand = if mine { MY_HAND } else { OTHER_HAND };
+        let in_hand = |card: &CardView| card.attached_to_target == Some(hand);
+        if let Some(index) = from_hand
+            .and_then(|id| self.cards.iter().position(|c| in_hand(c) && c.card_state.get_instance_id() == id))
+            .or_else(|| self.cards.iter().position(in_hand))
+        {
+            self.remove_at(index);
+        }
+    }
+    /// Takes the top card off a deck, one we never got to see.
+    pub fn remove_from_deck(&mut self, mine: bool) {
+        let deck = if mine { MY_DECK } else { OTHER_DECK };
+        if let Some(index) = self.cards.iter().position(|c| c.attached_to_target == Some(deck)) {
+            self.remove_at(index);
+        }
+    }
+    /// Takes the card with this id off wherever it is. Returns whether we had it.
+    pub fn remove_card(&mut self, card: InstanceId) -> bool {
+        let Some(index) = self.find_card(card) else {
+            return false;
+        };
+        self.remove_at(index);
+        true
+    }
+    /// Turns a card face-up, or shows what it became.
+    pub fn update_card(&mut self, card_state: &CardState, textures: &'texture std::collections::HashMap<std::string::String, macroquad::texture::Texture2D>) {
+        if let Some(index) = self.find_card(card_state.get_instance_id()) {
+            let card_view = &mut self.cards[index];
+            card_view.card_state = card_state.clone();
+            if let CardState::Revealed(_, card_id, _) = card_state {
                 card_view.texture = &textures[card_id];
             }
-            CardState::Hidden(instance_id) => {
-                card_view.card_state = card_state.clone();
-                card_view.texture = &textures["back"];
-                card_view.attached_to_target = Some(OTHER_HAND);
-                self.update_layout(OTHER_HAND);
-            }
+        }
+    }
+    pub fn set_counters(&mut self, card: InstanceId, counters: &Counters) {
+        if let Some(index) = self.find_card(card) {
+            *self.cards[index].card_state.counters_mut() = counters.clone();
+        }
+    }
+    fn find_card(&self, card: InstanceId) -> Option<usize> {
+        self.cards.iter().position(|c| c.card_state.get_instance_id() == card)
+    }
+    fn remove_at(&mut self, index: usize) {
+        // Drag and focus hold indices into the cards, which just shifted.
+        self.zoom_out_all_cards();
+        self.current_drag = None;
+        let card = self.cards.remove(index);
+        if let Some(target) = card.attached_to_target
+            && self.cards.iter().any(|c| c.attached_to_target == Some(target))
+        {
+            self.update_layout(target);
         }
     }
     pub fn update_layout(&mut self, target_id: usize) {
@@ -103,8 +136,7 @@ impl<'texture> Board<'texture> {
             .filter(|c| c.attached_to_target == Some(target_id))
             .collect();
         match target.target_type {
-            TargetType::BoardH => {}
-            TargetType::Hand => {
+            TargetType::Hand | TargetType::BoardH => {
                 let mut next_pos = target.anchor;
                 let offset = ((cards_per_target.len() - 1) as f32 * distance) / 2.0;
                 for card in cards_per_target.iter_mut().rev() {
@@ -112,10 +144,7 @@ impl<'texture> Board<'texture> {
                     next_pos = vec3(next_pos.x + distance, next_pos.y, next_pos.z);
                 }
             }
-            TargetType::Event => {}
-            TargetType::BoardV => {}
-            TargetType::Trash => {}
-            TargetType::Stack => {
+            TargetType::Stack | TargetType::Trash => {
                 let mut next_pos = target.anchor;
                 let offset = 0.02;
                 for card in cards_per_target {
@@ -127,6 +156,11 @@ impl<'texture> Board<'texture> {
         self.cards.sort_by(|a,b| b.position.y.total_cmp(&a.position.y));
 
     }
+    pub fn clear(&mut self) {
+        self.cards.clear();
+        self.current_drag = None;
+        self.has_focus = false;
+    }
     pub fn add_card_to_target(&mut self, mut card: CardView<'texture>, target_id: usize) {
         card.attached_to_target = Some(target_id);
         self.cards.push(card);
@@ -146,20 +180,17 @@ impl<'texture> Board<'texture> {
     }
     pub fn zoom_out_all_cards(&mut self) {
         for card in self.cards.iter_mut() {
-            self.current_focus = None;
+            self.has_focus = false;
             card.zoom_in(1.0)
         }
     }
     pub fn update(&mut self, mouse_world: Vec3, handler: &NodeHandler<()>, endpoint: Endpoint) {
         if self.current_drag.is_none() {
-            let is_focus = self.current_focus.is_some();
-            if let Some((index, card)) = self.check_intersection(mouse_world) {
+            let is_focus = self.has_focus;
+            if let Some((_, card)) = self.check_intersection(mouse_world) {
                 if !is_focus {
                     card.zoom_in(3.0);
-                    self.current_focus = Some(FocusInfo {
-                        selected_card: index,
-                        previous_scale: 1.0,
-                    })
+                    self.has_focus = true;
                 }
             } else {
                 self.zoom_out_all_cards();
@@ -170,54 +201,53 @@ impl<'texture> Board<'texture> {
             if let Some((index, card)) = self.check_intersection(mouse_world) {
                 card.is_grabbed = true;
                 match card.card_state {
-                    common::card::CardState::Revealed(instance_id, _) => {
+                    common::card::CardState::Revealed(..) => {
                         self.current_drag = Some(DragInfo {
                             selected_card: index,
-                            from_position: card.position,
                             drag_offset: mouse_world - card.position,
                             from_target_id: card.attached_to_target,
                         });
                     }
-                    common::card::CardState::Hidden(_) => {
+                    common::card::CardState::Hidden(..) => {
                         self.current_drag = Some(DragInfo {
                             selected_card: index,
-                            from_position: card.position,
                             drag_offset: mouse_world - card.position,
                             from_target_id: card.attached_to_target,
-                        });;
+                        });
                     }
                 };
             }
         } else if is_mouse_button_down(MouseButton::Left) {
             if let Some(drag) = &self.current_drag {
-                let card = &mut self.cards[drag.selected_card as usize];
+                let card = &mut self.cards[drag.selected_card];
                 card.position = mouse_world - drag.drag_offset;
             }
-        } else if is_mouse_button_released(MouseButton::Left) {
-            if let Some(drag) = &self.current_drag {
-                let card = &mut self.cards[drag.selected_card as usize];
-                let mut selected_target: Option<usize> = drag.from_target_id;
-                for (target) in &self.targets {
-                    if (target.can_drop || target.id == drag.from_target_id.unwrap()) && card.intersects_area(&target) {
-                        selected_target = Some(target.id);
-                        break;
-                    }
+        } else if is_mouse_button_released(MouseButton::Left)
+            && let Some(drag) = &self.current_drag
+        {
+            let card = &mut self.cards[drag.selected_card];
+            let mut selected_target: Option<usize> = drag.from_target_id;
+            for target in &self.targets {
+                if (target.can_drop || target.id == drag.from_target_id.unwrap()) && card.intersects_area(target) {
+                    selected_target = Some(target.id);
+                    break;
                 }
-                if let Some(target_id) = selected_target {
-                    #[allow(clippy::single_match)] match drag.from_target_id.unwrap()
-                    {
-                        MY_DECK=> if target_id == MY_HAND {
-                           let req =  DrawCard(card.card_state.get_instance_id());
-                            let output_data = bincode::serialize(&req).unwrap();
-                            handler.network().send(endpoint,&output_data);
-                       },
-                        _ => {}
-                    }
-                    card.attached_to_target = Some(target_id);
-                    self.update_layout(target_id);
+            }
+            if let Some(mut target_id) = selected_target {
+                #[allow(clippy::single_match)] match drag.from_target_id.unwrap()
+                {
+                    MY_DECK if target_id == MY_HAND => {
+                        let output_data = bincode::serialize(&ActionReq::DrawCard).unwrap();
+                        handler.network().send(endpoint,&output_data);
+                        // The card goes back on the deck, the server tells us what we drew.
+                        target_id = MY_DECK;
+                   },
+                    _ => {}
                 }
-                self.current_drag = None;
+                card.attached_to_target = Some(target_id);
+                self.update_layout(target_id);
             }
+            self.current_drag = None;
         }
     }
     pub fn draw(&self) {
@@ -1,7 +1,7 @@
-use common::card::CardState;
-use macroquad::prelude::{scene::camera_pos, *};
+use common::card::{CardState, Counter};
+use macroquad::prelude::*;
 
-use crate::board::{DropTarget, TargetType};
+use crate::board::DropTarget;
 
 pub struct CardView<'texture> {
     pub card_state: CardState,
@@ -41,8 +41,8 @@ impl<'texture> CardView<'texture> {
     }
     pub fn draw(&self) {
         match self.card_state {
-            CardState::Revealed(_, _) => {
-                draw_plane(self.position, self.size, Some(&self.texture), WHITE);
+            CardState::Revealed(..) => {
+                draw_plane(self.position, self.size, Some(self.texture), WHITE);
                 if self.zoom_in > 1.0 {
                     let new_position = vec3(
                         self.position.x,
@@ -52,13 +52,35 @@ impl<'texture> CardView<'texture> {
                     draw_plane(
                         new_position,
                         self.size * self.zoom_in,
-                        Some(&self.texture),
+                        Some(self.texture),
                         WHITE,
                     );
                 }
             }
-            CardState::Hidden(_) => {
-                draw_plane(self.position, self.size, Some(&self.texture), WHITE);
+            CardState::Hidden(..) => {
+                draw_plane(self.position, self.size, Some(self.texture), WHITE);
+            }
+        }
+        self.draw_counters();
+    }
+    /// One token per counter in a row along the bottom of the card, a row per kind.
+    fn draw_counters(&self) {
+        let radius = self.size.x * 0.08;
+        for (row, (counter, count)) in self.card_state.counters().iter().enumerate() {
+            let color = match counter {
+                Counter::Attunement => SKYBLUE,
+                Counter::Power => RED,
+                Counter::Mana => GOLD,
+                Counter::Virus => GREEN,
+            };
+            for i in 0..*count {
+                let center = self.position
+                    + vec3(
+                        -self.size.x + radius * (1.0 + 2.5 * i as f32),
+                        radius,
+                        self.size.y - radius * (1.0 + 2.5 * row as f32),
+                    );
+                draw_sphere(center, radius, None, color);
             }
         }
     }
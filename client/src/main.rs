@@ -1,29 +1,33 @@
 mod board;
 mod card_view;
+mod replay;
+mod table_view;
 use std::collections::HashMap;
 
 use common::CardId;
 use common::Response;
 use common::card::CardState;
 use common::card::Faction;
-use common::{ActionReq, InitReq, InitStateResponse};
-use macroquad::miniquad::{ElapsedQuery, window};
+use common::deck::{DeckChoice, load_deck_from_json};
+use common::card::CardData;
+use common::{ActionReq, InitReq, ServerTarget};
 use macroquad::prelude::*;
 use message_io::events::EventReceiver;
-use message_io::network::{Endpoint, NetEvent, SendStatus, Transport};
-use message_io::node::StoredNetEvent;
+use message_io::network::{Endpoint, SendStatus, Transport};
 use message_io::node::StoredNodeEvent;
 use message_io::node::{self, NodeHandler, NodeListener};
 
 use crate::board::Board;
 use crate::board::DropTarget;
+use crate::board::MY_BOARD;
 use crate::board::MY_DECK;
 use crate::board::MY_HAND;
 use crate::board::MY_TRASH;
+use crate::board::OTHER_BOARD;
 use crate::board::OTHER_DECK;
 use crate::board::OTHER_HAND;
 use crate::board::OTHER_TRASH;
-use crate::card_view::CardView; // <-- Using shared code!
+use crate::table_view::TableView;
 // Helper to store our networking items
 struct Net {
     handler: NodeHandler<()>,
@@ -72,23 +76,25 @@ fn ndc_to_world(m_inv: &Mat4, ndc_pos: Vec2) -> Vec3 {
     world_coord
 }
 
-fn other_faction(my_faction: Faction) -> Faction {
-    match my_faction {
-        Faction::Sanctum => Faction::Thief,
-        Faction::Thief => Faction::Sanctum,
-    }
-}
 
-fn get_color(faction: Faction, turn: Faction) -> Color {
-    if turn == faction {
-        Color::from_rgba(0, 255, 0, 255)
+// The board only handles drawing for now, everything else is on the keyboard.
+fn keyboard_action() -> Option<ActionReq> {
+    if is_key_pressed(KeyCode::M) {
+        Some(ActionReq::GainMana)
+    } else if is_key_pressed(KeyCode::H) {
+        Some(ActionReq::Infiltrate(ServerTarget::HandLair))
+    } else if is_key_pressed(KeyCode::D) {
+        Some(ActionReq::Infiltrate(ServerTarget::DeckLair))
+    } else if is_key_pressed(KeyCode::A) {
+        Some(ActionReq::Infiltrate(ServerTarget::DiscardLair))
+    } else if is_key_pressed(KeyCode::Space) {
+        Some(ActionReq::Continue)
+    } else if is_key_pressed(KeyCode::J) {
+        Some(ActionReq::JackOut)
     } else {
-        Color::from_rgba(255, 0, 0, 255)
+        None
     }
 }
-fn is_my_turn(my_faction: Faction, state: &InitStateResponse) -> bool {
-    my_faction == state.turn
-}
 
 fn window_conf() -> Conf {
     Conf {
@@ -100,14 +106,27 @@ fn window_conf() -> Conf {
     }
 }
 
-pub fn receive(receiver: &mut EventReceiver<StoredNodeEvent<()>>) -> Option<Response> {
+const SERVER_ADDRESS: &str = "127.0.0.1:8080";
+// How long a reconnection attempt gets before we dial again, about two seconds.
+const RECONNECT_FRAMES: u32 = 120;
+
+#[allow(clippy::large_enum_variant)]
+pub enum Incoming {
+    Response(Response),
+    Disconnected(Endpoint),
+}
+
+pub fn receive(receiver: &mut EventReceiver<StoredNodeEvent<()>>) -> Option<Incoming> {
     match receiver.try_receive() {
         Some(event) => match event {
             node::StoredNodeEvent::Network(net_event) => match net_event {
-                node::StoredNetEvent::Message(endpoint, data) => {
+                node::StoredNetEvent::Message(_, data) => {
                     let state: Response = bincode::deserialize(&data).unwrap();
                     dbg!("{:?}", &state);
-                    Some(state)
+                    Some(Incoming::Response(state))
+                }
+                node::StoredNetEvent::Disconnected(endpoint) => {
+                    Some(Incoming::Disconnected(endpoint))
                 }
                 _ => None,
             },
@@ -116,67 +135,55 @@ pub fn receive(receiver: &mut EventReceiver<StoredNodeEvent<()>>) -> Option<Resp
         None => None,
     }
 }
+/// Dials the server again until it takes `rejoin`, our session token or the
+/// table we were watching.
+async fn reconnect(handler: &NodeHandler<()>, rejoin: &ActionReq) -> Endpoint {
+    let bytes = bincode::serialize(rejoin).unwrap();
+    loop {
+        if let Ok((server, _)) = handler.network().connect(Transport::Ws, SERVER_ADDRESS) {
+            for _ in 0..RECONNECT_FRAMES {
+                if handler.network().send(server, &bytes) == SendStatus::Sent {
+                    return server;
+                }
+                next_frame().await;
+            }
+            handler.network().remove(server.resource_id());
+        }
+        next_frame().await;
+    }
+}
+
+async fn send_when_connected(handler: &NodeHandler<()>, server: Endpoint, request: &ActionReq) {
+    let bytes = bincode::serialize(request).unwrap();
+    while handler.network().send(server, &bytes) != SendStatus::Sent {
+        next_frame().await;
+    }
+}
+
 pub fn get_texture_from_card_state<'a>(c:&CardState, textures:&'a HashMap<CardId, Texture2D>) -> &'a Texture2D
 {
     match  c{
-        CardState::Revealed(_, cardid) => {
+        CardState::Revealed(_, cardid, _) => {
             &textures[cardid]
         }
-        CardState::Hidden(_) => {
+        CardState::Hidden(..) => {
             &textures["back"]
         }
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
-    let args: Vec<String> = std::env::args().collect();
-
-    prevent_quit();
-    let selected_fanction = match args.get(2).expect("Select faction").as_ref() {
-        "thief" => Faction::Thief, // The non-streamed version of tcp.
-        "sanctum" => Faction::Sanctum,
-        _ => panic!("{}", "Select thief or sanctum"),
-    };
-    let name = args.get(1).expect("Select Name");
-    let (handler, listener) = node::split::<()>();
-
-    let (server_id, _) = handler
-        .network()
-        .connect(Transport::Ws, "127.0.0.1:8080")
-        .unwrap();
-
-    let mut net = Net {
-        handler,
-        listener,
-        server_id,
-    };
-
-    let back_texture = load_texture("client/assets/cards/back.png").await.unwrap();
-    let (task, mut receiver) = net.listener.enqueue();
-    let bytes = bincode::serialize(&ActionReq::Init(InitReq {
-        name: name.clone(),
-        faction: selected_fanction,
-    }))
-    .unwrap();
-    let mut status = net.handler.network().send(net.server_id, &bytes);
-    while (status != SendStatus::Sent) {
-        status = net.handler.network().send(net.server_id, &bytes);
-        next_frame().await;
-    }
-    let mut response = None;
-    while let None = response {
-        response = receive(&mut receiver);
-    }
-    let camera = Camera3D {
+fn table_camera() -> Camera3D {
+    Camera3D {
         position: vec3(0.0, 5.0, 0.0),
         up: vec3(0., 0., -1.0),
         target: vec3(0., 0., 0.),
         projection: Projection::Perspective,
         ..Default::default()
-    };
-    let inv_matrix = camera.matrix().inverse();
-    let mut board = Board::new(vec![
+    }
+}
+
+fn create_board<'t>() -> Board<'t> {
+    Board::new(vec![
         DropTarget {
             id: OTHER_HAND,
             anchor: vec3(0.0, 0.0, -1.5),
@@ -198,6 +205,34 @@ async fn main() {
             target_type: board::TargetType::Stack,
             can_drop: false,
         },
+        DropTarget {
+            id: MY_TRASH,
+            anchor: vec3(-3.0, 0.0, 1.5),
+            size: vec2(1.0, 1.0),
+            target_type: board::TargetType::Trash,
+            can_drop: false,
+        },
+        DropTarget {
+            id: OTHER_TRASH,
+            anchor: vec3(3.0, 0.0, -1.5),
+            size: vec2(1.0, 1.0),
+            target_type: board::TargetType::Trash,
+            can_drop: false,
+        },
+        DropTarget {
+            id: OTHER_BOARD,
+            anchor: vec3(0.0, 0.0, -0.5),
+            size: vec2(10.0, 0.5),
+            target_type: board::TargetType::BoardH,
+            can_drop: false,
+        },
+        DropTarget {
+            id: MY_BOARD,
+            anchor: vec3(0.0, 0.0, 0.5),
+            size: vec2(10.0, 0.5),
+            target_type: board::TargetType::BoardH,
+            can_drop: false,
+        },
         DropTarget {
             id: MY_HAND,
             anchor: vec3(0.0, 0.0, 1.5),
@@ -205,52 +240,169 @@ async fn main() {
             target_type: board::TargetType::Hand,
             can_drop: true,
         },
-    ]);
+    ])
+}
 
+async fn load_textures(card_set: &HashMap<CardId, CardData>) -> HashMap<CardId, Texture2D> {
     let mut textures: HashMap<CardId, Texture2D> = HashMap::new();
-    let mut card_set = None;
-    let mut turn: Faction = Faction::Thief;
-    if let Some(response_data) = response { match response_data {
-        Response::Initial(init_state_response) => {
-            turn = init_state_response.turn;
-            card_set = Some(init_state_response.card_set);
-            for (id, card) in card_set.unwrap().iter() {
-                let texture = load_texture(&card.image_file).await.unwrap();
-                textures.insert(id.to_string(), texture);
-            }
-            textures.insert("back".to_string(),back_texture);
-            let mystate = init_state_response.my_state.unwrap();
-            let other_state = init_state_response.other_state.unwrap();
-            for c in mystate.get_common().hand.iter() {
-                board.add_card_to_target(CardView::new(c.clone(),get_texture_from_card_state(c,&textures)), MY_HAND);
-            }
-            for c in mystate.get_common().deck.iter() {
-                board.add_card_to_target(CardView::new(c.clone(), get_texture_from_card_state(c,&textures)), MY_DECK);
-            }
-            for c in other_state.get_common().hand.iter() {
-                board.add_card_to_target(CardView::new(c.clone(), get_texture_from_card_state(c,&textures)), OTHER_HAND);
+    for (id, card) in card_set.iter() {
+        let texture = load_texture(&card.image_file).await.unwrap();
+        textures.insert(id.to_string(), texture);
+    }
+    let back_texture = load_texture("client/assets/cards/back.png").await.unwrap();
+    textures.insert("back".to_string(), back_texture);
+    textures
+}
+
+#[macroquad::main(window_conf)]
+async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    prevent_quit();
+    // `client --replay <file>` plays back a recorded game, no server needed.
+    if args.get(1).map(String::as_str) == Some("--replay") {
+        let path = args.get(2).expect("Select a replay file");
+        let file = common::replay::load_replay(path).unwrap_or_else(|err| panic!("{}: {}", path, err));
+        replay::run(file).await;
+        return;
+    }
+    let (handler, listener) = node::split::<()>();
+
+    let (server_id, _) = handler
+        .network()
+        .connect(Transport::Ws, SERVER_ADDRESS)
+        .unwrap();
+
+    let mut net = Net {
+        handler,
+        listener,
+        server_id,
+    };
+    let (_task, mut receiver) = net.listener.enqueue();
+
+    if args.get(1).map(String::as_str) == Some("--tables") {
+        send_when_connected(&net.handler, net.server_id, &ActionReq::ListTables).await;
+        loop {
+            if let Some(Incoming::Response(Response::Tables { tables })) = receive(&mut receiver) {
+                for table in tables {
+                    println!(
+                        "{}: Sanctum {} / Thief {}{}",
+                        table.name,
+                        table.sanctum.as_deref().unwrap_or("-"),
+                        table.thief.as_deref().unwrap_or("-"),
+                        if table.started { " (playing)" } else { "" }
+                    );
+                }
+                net.handler.stop();
+                return;
             }
-            for c in other_state.get_common().deck.iter() {
-                board.add_card_to_target(CardView::new(c.clone(), get_texture_from_card_state(c,&textures)), OTHER_DECK);
+        }
+    }
+    // `client --spectate <table>` watches a table from the Sanctum's side instead of playing.
+    let spectate = match args.get(1).map(String::as_str) {
+        Some("--spectate") => Some(args.get(2).expect("Select table").clone()),
+        _ => None,
+    };
+    let spectating = spectate.is_some();
+    // `client --resume <token>` takes back a seat, say after the server restored a snapshot.
+    let resume = match args.get(1).map(String::as_str) {
+        Some("--resume") => Some(args.get(2).expect("Select session token").clone()),
+        _ => None,
+    };
+    let mut selected_fanction = match (&spectate, &resume) {
+        // A resumed seat's faction comes with the initial state.
+        (Some(_), _) | (_, Some(_)) => Faction::Sanctum,
+        (None, None) => match args.get(2).expect("Select faction").as_ref() {
+            "thief" => Faction::Thief, // The non-streamed version of tcp.
+            "sanctum" => Faction::Sanctum,
+            _ => panic!("{}", "Select thief or sanctum"),
+        },
+    };
+
+    // Whatever gets us back to the table after losing the connection.
+    let mut rejoin = match (spectate, resume) {
+        (Some(table), _) => ActionReq::Spectate(table),
+        (None, Some(token)) => ActionReq::Resume(token),
+        (None, None) => {
+            let name = args.get(1).expect("Select Name");
+            // Either a deck list file or the name of a deck in the server's decks directory.
+            let deck = match args.get(3) {
+                Some(path) if path.ends_with(".json") => {
+                    DeckChoice::List(load_deck_from_json(path).expect("Unreadable deck list"))
+                }
+                Some(name) => DeckChoice::Named(name.clone()),
+                None => DeckChoice::Named(format!("{}_starter", args[2])),
+            };
+            let table = args.get(4).cloned().unwrap_or("default".to_string());
+            let [create, init] = InitReq {
+                table,
+                name: name.clone(),
+                faction: selected_fanction,
+                deck,
             }
+            .join_requests();
+            send_when_connected(&net.handler, net.server_id, &create).await;
+            init
         }
-        Response::DrawCard {  card: ref c } => {
-            board.draw_card(c, &textures);
+    };
+    send_when_connected(&net.handler, net.server_id, &rejoin).await;
+    let mut response = None;
+    while !matches!(
+        response,
+        Some(Response::Initial(_) | Response::InitRejected { .. })
+    ) {
+        match receive(&mut receiver) {
+            Some(Incoming::Response(Response::Joined { token })) => {
+                rejoin = ActionReq::Resume(token);
+            }
+            Some(Incoming::Response(r)) => response = Some(r),
+            Some(Incoming::Disconnected(_)) => panic!("Lost the server before the game started"),
+            None => (),
         }
-    } }
+    }
+    if let Some(Response::InitRejected { reason }) = &response {
+        panic!("Server rejected us: {}", reason);
+    }
+    if let ActionReq::Resume(_) = rejoin
+        && let Some(Response::Initial(state)) = &response
+    {
+        selected_fanction = state.my_state.as_ref().unwrap().faction();
+    }
+    let camera = table_camera();
+    let inv_matrix = camera.matrix().inverse();
+    let mut board = create_board();
+    let mut view = TableView::new(selected_fanction);
+    let textures = match &response {
+        Some(Response::Initial(init_state_response)) => load_textures(&init_state_response.card_set).await,
+        _ => unreachable!(),
+    };
     //  board.add_card_to_target(card, 1);
     // board.add_card_to_target(card1, 1);
     loop {
 
         clear_background(BLACK);
 
-        let mut response = receive(&mut receiver);
-        if let Some(response_data) = response { match response_data {
-            Response::DrawCard { card: ref c } => {
-                board.draw_card(c, &textures);
-            }
-            _=> ()
-        } }
+        // The initial state goes through here too, so a resume can lay out the board again.
+        let response = match response.take() {
+            Some(initial) => Some(initial),
+            None => match receive(&mut receiver) {
+                Some(Incoming::Response(response)) => Some(response),
+                Some(Incoming::Disconnected(endpoint)) if endpoint == net.server_id => {
+                    println!("Lost the connection, trying to resume");
+                    net.server_id = reconnect(&net.handler, &rejoin).await;
+                    None
+                }
+                _ => None,
+            },
+        };
+        if let Some(response) = response {
+            view.apply(&mut board, &response, &textures);
+        }
+        if !spectating && let Some(action) = keyboard_action() {
+            view.rejected = None;
+            let output_data = bincode::serialize(&action).unwrap();
+            net.handler.network().send(net.server_id, &output_data);
+        }
         set_camera(&camera);
 
         let mouse_world_pos = ndc_to_world(&inv_matrix, mouse_position_local());
@@ -258,20 +410,7 @@ async fn main() {
         board.draw();
 
         set_default_camera();
-        draw_text(
-            &format!("{}", selected_fanction),
-            10.0,
-            screen_height() - 50.0,
-            40.0,
-            get_color(selected_fanction, turn),
-        );
-        draw_text(
-            &format!("{}", other_faction(selected_fanction)),
-            10.0,
-            50.0,
-            40.0,
-            get_color(other_faction(selected_fanction), turn),
-        );
+        view.draw();
         if is_quit_requested() {
             net.handler.stop();
             break;
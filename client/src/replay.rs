@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use common::card::Faction;
+use common::replay::ReplayFile;
+use common::{CardId, Response};
+use macroquad::prelude::*;
+
+use crate::board::Board;
+use crate::table_view::TableView;
+use crate::{create_board, load_textures, table_camera};
+
+// How long each event stays on screen while playing.
+const SECONDS_PER_EVENT: f64 = 0.75;
+// How far Page Up / Page Down jump.
+const SEEK_STEP: usize = 10;
+
+/// Plays a recorded game on the board, watched from the Sanctum's side.
+struct ReplayView<'t> {
+    file: ReplayFile,
+    textures: &'t HashMap<CardId, Texture2D>,
+    board: Board<'t>,
+    view: TableView,
+    // Number of events applied so far.
+    step: usize,
+    omniscient: bool,
+}
+impl<'t> ReplayView<'t> {
+    fn new(file: ReplayFile, textures: &'t HashMap<CardId, Texture2D>) -> Self {
+        let mut replay = Self {
+            file,
+            textures,
+            board: create_board(),
+            view: TableView::new(Faction::Sanctum),
+            step: 0,
+            omniscient: true,
+        };
+        replay.seek(0);
+        replay
+    }
+
+    fn is_finished(&self) -> bool {
+        self.step >= self.file.events.len()
+    }
+
+    fn step_forward(&mut self) {
+        let Some(event) = self.file.events.get(self.step) else {
+            return;
+        };
+        for update in event.updates() {
+            self.view
+                .apply(&mut self.board, update.spectator_view(self.omniscient), self.textures);
+        }
+        self.step += 1;
+    }
+
+    /// Lays the board out again from the start and plays it up to `step`,
+    /// the board can't undo a move so going back means starting over.
+    fn seek(&mut self, step: usize) {
+        let mut initial = self.file.header.initial.clone();
+        // The recording starts from what an omniscient spectator sees.
+        if !self.omniscient {
+            for state in [&mut initial.my_state, &mut initial.other_state]
+                .into_iter()
+                .flatten()
+            {
+                state.get_common_mut().hand.clear();
+            }
+        }
+        self.view = TableView::new(Faction::Sanctum);
+        self.view
+            .apply(&mut self.board, &Response::Initial(initial), self.textures);
+        self.step = 0;
+        while self.step < step.min(self.file.events.len()) {
+            self.step_forward();
+        }
+    }
+
+    fn draw(&self, playing: bool) {
+        self.board.draw();
+        set_default_camera();
+        self.view.draw();
+        draw_text(
+            &format!(
+                "{} {}/{} {} | Space play/pause, arrows step, PgUp/PgDn/Home/End seek, O {} view",
+                self.file.header.table,
+                self.step,
+                self.file.events.len(),
+                if playing { "playing" } else { "paused" },
+                if self.omniscient { "omniscient" } else { "public" }
+            ),
+            10.0,
+            screen_height() - 10.0,
+            24.0,
+            GRAY,
+        );
+    }
+}
+
+pub async fn run(file: ReplayFile) {
+    let textures = load_textures(&file.header.initial.card_set).await;
+    let camera = table_camera();
+    let mut replay = ReplayView::new(file, &textures);
+    let mut playing = false;
+    let mut last_step_at = get_time();
+    loop {
+        clear_background(BLACK);
+
+        if is_key_pressed(KeyCode::Space) {
+            playing = !playing;
+            last_step_at = get_time();
+        } else if is_key_pressed(KeyCode::Right) {
+            replay.step_forward();
+        } else if is_key_pressed(KeyCode::Left) {
+            replay.seek(replay.step.saturating_sub(1));
+        } else if is_key_pressed(KeyCode::PageDown) {
+            replay.seek(replay.step + SEEK_STEP);
+        } else if is_key_pressed(KeyCode::PageUp) {
+            replay.seek(replay.step.saturating_sub(SEEK_STEP));
+        } else if is_key_pressed(KeyCode::Home) {
+            replay.seek(0);
+        } else if is_key_pressed(KeyCode::End) {
+            replay.seek(replay.file.events.len());
+        } else if is_key_pressed(KeyCode::O) {
+            replay.omniscient = !replay.omniscient;
+            replay.seek(replay.step);
+        }
+        if playing && get_time() - last_step_at >= SECONDS_PER_EVENT {
+            replay.step_forward();
+            last_step_at = get_time();
+            playing = !replay.is_finished();
+        }
+
+        set_camera(&camera);
+        replay.draw(playing);
+
+        if is_quit_requested() {
+            break;
+        }
+        next_frame().await;
+    }
+}
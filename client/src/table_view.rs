@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use common::card::{CardState, Counters, Faction};
+use common::{BasicStats, CardId, InitStateResponse, InstanceId, PlayerStateResponse, Response, ServerTarget};
+use macroquad::prelude::*;
+
+use crate::board::{Board, MY_BOARD, MY_DECK, MY_HAND, MY_TRASH, OTHER_BOARD, OTHER_DECK, OTHER_HAND, OTHER_TRASH};
+use crate::card_view::CardView;
+use crate::get_texture_from_card_state;
+
+/// Stands in for a card in a hidden zone, the server doesn't send us their ids.
+const FACE_DOWN: CardState = CardState::Hidden(0, Counters::new());
+
+pub fn other_faction(my_faction: Faction) -> Faction {
+    match my_faction {
+        Faction::Sanctum => Faction::Thief,
+        Faction::Thief => Faction::Sanctum,
+    }
+}
+
+fn get_color(faction: Faction, turn: Faction) -> Color {
+    if turn == faction {
+        Color::from_rgba(0, 255, 0, 255)
+    } else {
+        Color::from_rgba(255, 0, 0, 255)
+    }
+}
+
+fn format_stats(stats: &BasicStats) -> String {
+    format!(
+        "Mana: {} Stamina: {} Score: {}",
+        stats.mana_pool, stats.stamina, stats.score
+    )
+}
+
+/// Every card a side has on the table, as far as that state shows them to us.
+fn installed_cards(state: &PlayerStateResponse) -> Vec<&CardState> {
+    match state {
+        PlayerStateResponse::Sanctum { specific, .. } => [&specific.hand_lair, &specific.deck_lair, &specific.discard_lair]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .chain(specific.remotes.iter().flatten().flat_map(|remote| remote.wards.iter().chain(remote.contents.iter())))
+            .collect(),
+        PlayerStateResponse::Thief { specific, .. } => [&specific.spell_slots, &specific.gear_slots, &specific.ally_slots]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .collect(),
+    }
+}
+
+/// Lays out a fresh board from a full state, as sent on joining or resuming.
+pub fn populate_board<'t>(
+    board: &mut Board<'t>,
+    state: &InitStateResponse,
+    textures: &'t HashMap<CardId, Texture2D>,
+) {
+    board.clear();
+    let mystate = state.my_state.as_ref().unwrap();
+    let other_state = state.other_state.as_ref().unwrap();
+    // Hands we can't see, and decks, only come as counts so they are filled with card backs.
+    for (player, hand, deck, trash, installed) in [
+        (mystate, MY_HAND, MY_DECK, MY_TRASH, MY_BOARD),
+        (other_state, OTHER_HAND, OTHER_DECK, OTHER_TRASH, OTHER_BOARD),
+    ] {
+        let common = player.get_common();
+        for c in common.hand.iter() {
+            board.add_card_to_target(CardView::new(c.clone(),get_texture_from_card_state(c,textures)), hand);
+        }
+        for _ in common.hand.len() as u32..common.hand_size {
+            board.add_card_to_target(CardView::new(FACE_DOWN, &textures["back"]), hand);
+        }
+        for _ in 0..common.deck_size {
+            board.add_card_to_target(CardView::new(FACE_DOWN, &textures["back"]), deck);
+        }
+        for c in common.discard.iter() {
+            board.add_card_to_target(CardView::new(c.clone(), get_texture_from_card_state(c, textures)), trash);
+        }
+        for c in installed_cards(player) {
+            board.add_card_to_target(CardView::new(c.clone(), get_texture_from_card_state(c, textures)), installed);
+        }
+    }
+}
+
+/// What's shown around the board, kept up to date from server responses.
+/// `faction` is the side sitting at the bottom of the screen.
+pub struct TableView {
+    pub faction: Faction,
+    pub turn: Faction,
+    pub my_stats: BasicStats,
+    pub other_stats: BasicStats,
+    pub infiltration: Option<String>,
+    infiltration_target: Option<ServerTarget>,
+    pub game_over: Option<String>,
+    // Why the server refused our last action, shown until we try another one.
+    pub rejected: Option<String>,
+    pub opponent_connected: bool,
+}
+impl TableView {
+    pub fn new(faction: Faction) -> Self {
+        let stats = BasicStats { mana_pool: 0, stamina: 0, score: 0 };
+        Self {
+            faction,
+            turn: Faction::Sanctum,
+            my_stats: stats,
+            other_stats: stats,
+            infiltration: None,
+            infiltration_target: None,
+            game_over: None,
+            rejected: None,
+            opponent_connected: true,
+        }
+    }
+
+    pub fn apply<'t>(
+        &mut self,
+        board: &mut Board<'t>,
+        response: &Response,
+        textures: &'t HashMap<CardId, Texture2D>,
+    ) {
+        match response {
+            Response::Initial(state) => {
+                populate_board(board, state, textures);
+                self.turn = state.turn;
+                self.my_stats = state.my_state.as_ref().unwrap().get_common().stats;
+                self.other_stats = state.other_state.as_ref().unwrap().get_common().stats;
+                self.infiltration = state
+                    .infiltration
+                    .map(|(target, step)| format!("Infiltrating {:?}: {:?}", target, step));
+                self.infiltration_target = state.infiltration.map(|(target, _)| target);
+                self.game_over = state
+                    .game_over
+                    .map(|(winner, reason)| format!("{} wins ({:?})", winner, reason));
+                self.opponent_connected = true;
+            }
+            Response::InitRejected { reason } => {
+                self.game_over = Some(format!("Couldn't resume: {}", reason));
+            }
+            Response::PlayerConnection { faction, connected } if *faction != self.faction => {
+                self.opponent_connected = *connected;
+            }
+            Response::DrawCard { faction, card } => {
+                board.draw_card(*faction == self.faction, card, textures);
+            }
+            Response::Discarded { faction, card, from_hand } | Response::Played { faction, card, from_hand } => {
+                let mine = *faction == self.faction;
+                board.remove_from_hand(mine, *from_hand);
+                let trash = if mine { MY_TRASH } else { OTHER_TRASH };
+                board.add_card_to_target(CardView::new(card.clone(), get_texture_from_card_state(card, textures)), trash);
+            }
+            Response::Installed { card, from_hand, .. } => {
+                self.install(board, Faction::Sanctum, card, *from_hand, textures);
+            }
+            Response::RigInstalled { card, from_hand } => {
+                self.install(board, Faction::Thief, card, *from_hand, textures);
+            }
+            Response::Reveal { card } => {
+                board.update_card(card, textures);
+            }
+            Response::CountersChanged { card, counters } => {
+                board.set_counters(*card, counters);
+            }
+            Response::Scored { faction: Faction::Sanctum, from, .. } => {
+                board.remove_card(*from);
+            }
+            Response::Scored { from, .. } => {
+                // A card stolen out of a hidden hand or off the deck is only
+                // a card back to us, the lair being infiltrated says which.
+                let seen = board.remove_card(*from);
+                let sanctum_is_mine = self.faction == Faction::Sanctum;
+                match self.infiltration_target {
+                    _ if seen => (),
+                    Some(ServerTarget::HandLair) => board.remove_from_hand(sanctum_is_mine, None),
+                    Some(ServerTarget::DeckLair) => board.remove_from_deck(sanctum_is_mine),
+                    _ => (),
+                }
+            }
+            Response::StatsChanged { faction, stats } => {
+                if *faction == self.faction {
+                    self.my_stats = *stats;
+                } else {
+                    self.other_stats = *stats;
+                }
+            }
+            Response::TurnStarted { turn, .. } => {
+                self.turn = *turn;
+            }
+            Response::Infiltration { target, step } => {
+                self.infiltration = Some(format!("Infiltrating {:?}: {:?}", target, step));
+                self.infiltration_target = Some(*target);
+            }
+            Response::InfiltrationEnded { .. } => {
+                self.infiltration = None;
+                self.infiltration_target = None;
+            }
+            Response::GameOver { winner, reason } => {
+                self.infiltration = None;
+                self.infiltration_target = None;
+                self.game_over = Some(format!("{} wins ({:?})", winner, reason));
+            }
+            Response::ActionRejected { reason } => {
+                self.rejected = Some(reason.to_string());
+            }
+            _ => (),
+        }
+    }
+
+    fn install<'t>(
+        &self,
+        board: &mut Board<'t>,
+        owner: Faction,
+        card: &CardState,
+        from_hand: Option<InstanceId>,
+        textures: &'t HashMap<CardId, Texture2D>,
+    ) {
+        let mine = owner == self.faction;
+        board.remove_from_hand(mine, from_hand);
+        let target = if mine { MY_BOARD } else { OTHER_BOARD };
+        board.add_card_to_target(CardView::new(card.clone(), get_texture_from_card_state(card, textures)), target);
+    }
+
+    pub fn draw(&self) {
+        draw_text(
+            &format!("{} {}", self.faction, format_stats(&self.my_stats)),
+            10.0,
+            screen_height() - 50.0,
+            40.0,
+            get_color(self.faction, self.turn),
+        );
+        draw_text(
+            &format!("{} {}", other_faction(self.faction), format_stats(&self.other_stats)),
+            10.0,
+            50.0,
+            40.0,
+            get_color(other_faction(self.faction), self.turn),
+        );
+        if let Some(infiltration) = &self.infiltration {
+            draw_text(infiltration, 10.0, screen_height() / 2.0, 40.0, YELLOW);
+        }
+        if !self.opponent_connected {
+            draw_text("Opponent disconnected, waiting for them to come back", 10.0, 100.0, 30.0, ORANGE);
+        }
+        if let Some(rejected) = &self.rejected {
+            draw_text(rejected, 10.0, screen_height() - 100.0, 30.0, RED);
+        }
+        if let Some(game_over) = &self.game_over {
+            draw_text(game_over, 10.0, screen_height() / 2.0, 80.0, WHITE);
+        }
+    }
+}
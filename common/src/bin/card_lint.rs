@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use common::card::{
+    CardData, CardType, Counter, CounterSpellSubType, Effect, Faction, WardSubType,
+    full_break_cost, missing_images, read_cards, validate_cards,
+};
+
+const IMAGES_DIRECTORY: &str = "client/assets/cards/";
+
+fn type_name(data: &CardType) -> &'static str {
+    match data {
+        CardType::AncientArtifact { .. } => "AncientArtifact",
+        CardType::Ward { .. } => "Ward",
+        CardType::Asset { .. } => "Asset",
+        CardType::Operation { .. } => "Operation",
+        CardType::CounterSpell { .. } => "CounterSpell",
+        CardType::Event { .. } => "Event",
+        CardType::MagicalGear => "MagicalGear",
+        CardType::Ally => "Ally",
+    }
+}
+
+/// Every effect the server will actually resolve for the card.
+fn mechanics(card: &CardData) -> Vec<Effect> {
+    let mut effects = card.effects.clone();
+    if let CardType::Ward { subroutines, .. } = &card.data {
+        effects.extend(subroutines);
+    }
+    for ability in card.counter_abilities.iter() {
+        effects.extend(&ability.effects);
+    }
+    effects
+}
+
+/// Words in card text that promise a mechanic.
+const KEYWORDS: [&str; 7] = [
+    "gain",
+    "draw",
+    "backlash",
+    "end the infiltration",
+    "counter",
+    "strength",
+    "break",
+];
+
+/// Whether the card has something that does what the keyword says.
+fn implements(keyword: &str, card: &CardData) -> bool {
+    let effects = mechanics(card);
+    match keyword {
+        "gain" => effects
+            .iter()
+            .any(|effect| matches!(effect, Effect::GainMana(_))),
+        "draw" => effects
+            .iter()
+            .any(|effect| matches!(effect, Effect::Draw(_))),
+        "backlash" => effects
+            .iter()
+            .any(|effect| matches!(effect, Effect::DealDamage(_))),
+        "end the infiltration" => effects.contains(&Effect::EndInfiltration),
+        "counter" => effects
+            .iter()
+            .any(|effect| matches!(effect, Effect::PlaceCounter(..))),
+        "strength" => matches!(card.data, CardType::CounterSpell { pump: Some(_), .. }),
+        "break" => matches!(card.data, CardType::CounterSpell { .. }),
+        _ => true,
+    }
+}
+
+fn print_cards_per_type(cards: &[(String, CardData)]) {
+    println!("Cards per faction and type");
+    for faction in [Faction::Sanctum, Faction::Thief] {
+        let mut types: BTreeMap<&str, u32> = BTreeMap::new();
+        for (_, card) in cards.iter().filter(|(_, card)| card.faction == faction) {
+            *types.entry(type_name(&card.data)).or_default() += 1;
+        }
+        let total: u32 = types.values().sum();
+        let types: Vec<String> = types
+            .iter()
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect();
+        println!("  {}: {} ({})", faction, total, types.join(", "));
+    }
+}
+
+fn print_cost_curves(cards: &[(String, CardData)]) {
+    println!("Cost curve");
+    for faction in [Faction::Sanctum, Faction::Thief] {
+        let mut curve: BTreeMap<u32, u32> = BTreeMap::new();
+        for (_, card) in cards.iter().filter(|(_, card)| card.faction == faction) {
+            if let Some(cost) = card.data.cost() {
+                *curve.entry(cost).or_default() += 1;
+            }
+        }
+        println!("  {}", faction);
+        for (cost, count) in curve {
+            println!("  {:>4} | {} {}", cost, "#".repeat(count as usize), count);
+        }
+    }
+}
+
+/// For each ward subtype, what each counterspell of the Thief pays to fully
+/// break the strongest ward of it, and whether any of them can at all.
+fn print_coverage(cards: &[(String, CardData)]) {
+    println!("Ward strength vs counterspell coverage");
+    for subtype in [WardSubType::Glyph, WardSubType::Rune, WardSubType::Guardian] {
+        let strongest = cards
+            .iter()
+            .filter_map(|(_, card)| match &card.data {
+                CardType::Ward {
+                    subtype: ward_subtype,
+                    strength,
+                    subroutines,
+                    ..
+                } if *ward_subtype == subtype => Some((*strength, subroutines.len(), card)),
+                _ => None,
+            })
+            .max_by_key(|(strength, subroutines, _)| (*strength, *subroutines))
+            .map(|(_, _, card)| card);
+        let breakers: Vec<(&str, Option<u32>)> = cards
+            .iter()
+            .filter(|(_, card)| {
+                matches!(&card.data, CardType::CounterSpell { subtype: breaker, .. }
+                    if breaker.breaks() == subtype)
+            })
+            .map(|(id, card)| {
+                let cost = strongest.map_or(Some(0), |ward| full_break_cost(card, ward));
+                (id.as_str(), cost)
+            })
+            .collect();
+        let covered = strongest.is_none() || breakers.iter().any(|(_, cost)| cost.is_some());
+        let breakers: Vec<String> = breakers
+            .iter()
+            .map(|(id, cost)| match cost {
+                Some(cost) => format!("{} {} mana", id, cost),
+                None => format!("{} can't", id),
+            })
+            .collect();
+        println!(
+            "  {:?}: strongest ward {}, {:?} {}{}",
+            subtype,
+            strongest.map_or("-".to_string(), |ward| format!(
+                "{} {}",
+                ward.id,
+                ward_strength(ward)
+            )),
+            breaker_subtype(subtype),
+            match breakers.is_empty() {
+                true => "none".to_string(),
+                false => breakers.join(", "),
+            },
+            if covered { "" } else { "  <- not covered" }
+        );
+    }
+}
+
+fn ward_strength(ward: &CardData) -> u32 {
+    match ward.data {
+        CardType::Ward { strength, .. } => strength,
+        _ => 0,
+    }
+}
+
+fn breaker_subtype(subtype: WardSubType) -> CounterSpellSubType {
+    [
+        CounterSpellSubType::Fracter,
+        CounterSpellSubType::Decoder,
+        CounterSpellSubType::Killer,
+    ]
+    .into_iter()
+    .find(|breaker| breaker.breaks() == subtype)
+    .unwrap()
+}
+
+/// Keywords in card text that nothing on the card implements.
+fn keyword_warnings(cards: &[(String, CardData)]) -> Vec<String> {
+    let mut warnings = vec![];
+    for (id, card) in cards {
+        let text = card.text.to_lowercase();
+        for keyword in KEYWORDS {
+            if text.contains(keyword) && !implements(keyword, card) {
+                warnings.push(format!(
+                    "{}: text mentions \"{}\" but no mechanic does it",
+                    id, keyword
+                ));
+            }
+        }
+        // Attunement counters count towards scoring, any other kind needs an ability to spend it.
+        for effect in mechanics(card) {
+            if let Effect::PlaceCounter(counter, _) = effect
+                && counter != Counter::Attunement
+                && !card
+                    .counter_abilities
+                    .iter()
+                    .any(|ability| ability.counter == counter)
+            {
+                warnings.push(format!(
+                    "{}: places {:?} counters but nothing spends them",
+                    id, counter
+                ));
+            }
+        }
+        if !card.image_file.starts_with(IMAGES_DIRECTORY) {
+            warnings.push(format!(
+                "{}: image {} isn't under {}",
+                id, card.image_file, IMAGES_DIRECTORY
+            ));
+        }
+    }
+    warnings
+}
+
+/// `card_lint [cards.json]`, run from the repository root. Fails when the
+/// server would refuse to load the file, or the client couldn't find an image.
+fn main() -> ExitCode {
+    let path = std::env::args().nth(1).unwrap_or("cards.json".to_string());
+    let (cards, mut errors) = match read_cards(&path) {
+        Ok(read) => read,
+        Err(errors) => {
+            eprint!("{}", errors);
+            return ExitCode::FAILURE;
+        }
+    };
+    errors.extend(validate_cards(&cards));
+    errors.extend(missing_images(&cards));
+
+    println!("{}: {} cards", path, cards.len());
+    print_cards_per_type(&cards);
+    print_cost_curves(&cards);
+    print_coverage(&cards);
+
+    let warnings = keyword_warnings(&cards);
+    if !warnings.is_empty() {
+        println!("Warnings");
+        for warning in warnings.iter() {
+            println!("  {}", warning);
+        }
+    }
+    if !errors.is_empty() {
+        println!("Errors");
+        for error in errors.iter() {
+            println!("  {}", error);
+        }
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
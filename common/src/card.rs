@@ -1,11 +1,15 @@
-use std::{collections::HashMap, fmt, fs};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::{fmt, fs};
 
-use serde::{Deserialize, Serialize};
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::value::RawValue;
 
 use crate::{CardId, InstanceId};
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
-pub struct Mana(u32);
+pub struct Mana(pub u32);
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
 pub enum Faction {
@@ -21,7 +25,7 @@ impl fmt::Display for Faction {
     }
 }
 
-#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
 pub enum WardSubType {
     Glyph,    // (Barrier)
     Rune,     // (Code Gate)
@@ -33,14 +37,52 @@ pub enum AssetSubType {
     Ambush,
     Ritual,
 }
-#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
 pub enum CounterSpellSubType {
     Fracter, // (Breaks Glyphs)
     Decoder, // (Breaks Runes)
     Killer,  // (Breaks Guardians)
 }
+impl CounterSpellSubType {
+    pub fn breaks(&self) -> WardSubType {
+        match self {
+            CounterSpellSubType::Fracter => WardSubType::Glyph,
+            CounterSpellSubType::Decoder => WardSubType::Rune,
+            CounterSpellSubType::Killer => WardSubType::Guardian,
+        }
+    }
+}
+
+/// Kinds of counters that sit on a card instance.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Counter {
+    Attunement, // (Advancement)
+    Power,
+    Mana,  // (Credits)
+    Virus,
+}
+/// How many of each kind of counter a card has, kinds with none are left out.
+pub type Counters = BTreeMap<Counter, u32>;
+
+// Building blocks for what cards do, interpreted by the server. A Ward
+// subroutine is a single effect.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub enum Effect {
+    GainMana(u32),
+    Draw(u32),
+    DealDamage(u32), // Magical Backlash, always dealt to the Thief
+    EndInfiltration,
+    PlaceCounter(Counter, u32), // On the card itself
+}
 
+// "X 💧: +Y Strength" or "X 💧: Break Y subroutines"
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct PaidAbility {
+    pub cost: Mana,
+    pub amount: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum CardType {
     AncientArtifact {
         vp: u32,
@@ -50,6 +92,7 @@ pub enum CardType {
         subtype: WardSubType,
         cost: Mana,
         strength: u32,
+        subroutines: Vec<Effect>,
     },
     Asset {
         subtype: AssetSubType,
@@ -65,6 +108,8 @@ pub enum CardType {
         cost: Mana,
         strength: u32,
         focus_cost: u32, // Replaces "SpellSlot"
+        pump: Option<PaidAbility>,
+        break_subroutines: PaidAbility,
     },
     Event {
         cost: Mana,
@@ -73,23 +118,48 @@ pub enum CardType {
     Ally,        // No stats, just text
 }
 
+impl CardType {
+    /// Mana it takes to play, install or rez the card, if it costs any.
+    pub fn cost(&self) -> Option<u32> {
+        match self {
+            CardType::Ward { cost, .. }
+            | CardType::Asset { cost, .. }
+            | CardType::Operation { cost, .. }
+            | CardType::CounterSpell { cost, .. }
+            | CardType::Event { cost } => Some(cost.0),
+            CardType::AncientArtifact { .. } | CardType::MagicalGear | CardType::Ally => None,
+        }
+    }
+}
+
 // --- 3. The final "Card" struct is clean and simple ---
+// Counters are public, even on a face-down card.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum CardState {
-    Revealed(InstanceId, CardId),
-    Hidden(InstanceId),
+    Revealed(InstanceId, CardId, Counters),
+    Hidden(InstanceId, Counters),
 }
 impl CardState {
     pub fn get_card_id(&self) -> Option<CardId> {
         match self {
-            CardState::Revealed(instance_id, card_id) => Some(card_id.clone()),
-            CardState::Hidden(_) => None,
+            CardState::Revealed(_, card_id, _) => Some(card_id.clone()),
+            CardState::Hidden(..) => None,
         }
     }
     pub fn get_instance_id(&self) -> InstanceId {
         match self {
-            CardState::Revealed(instance_id, _) => *instance_id,
-            CardState::Hidden(instance_id) => *instance_id,
+            CardState::Revealed(instance_id, ..) => *instance_id,
+            CardState::Hidden(instance_id, _) => *instance_id,
+        }
+    }
+    pub fn counters(&self) -> &Counters {
+        match self {
+            CardState::Revealed(_, _, counters) | CardState::Hidden(_, counters) => counters,
+        }
+    }
+    pub fn counters_mut(&mut self) -> &mut Counters {
+        match self {
+            CardState::Revealed(_, _, counters) | CardState::Hidden(_, counters) => counters,
         }
     }
 }
@@ -102,13 +172,400 @@ pub struct CardData {
     pub image_file: String,
     // The enum holds all the unique data
     pub data: CardType,
+    // Resolved when an Event or Operation is played, or an artifact attuned
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    // What spending each kind of counter on the card does
+    #[serde(default)]
+    pub counter_abilities: Vec<CounterAbility>,
+}
+
+/// "Spend 1 `counter`: `effects`"
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CounterAbility {
+    pub counter: Counter,
+    pub effects: Vec<Effect>,
 }
 
-pub fn load_cards_from_json(
-    file_path: &str,
-) -> Result<HashMap<String, CardData>, Box<dyn std::error::Error>> {
-    let json_string = fs::read_to_string(file_path)?;
-    let cards: HashMap<String, CardData> = serde_json::from_str(&json_string)?;
+/// Mana `counterspell` needs to match the strength of `ward` and break all of its
+/// subroutines, or `None` if it can't get through that ward at all.
+pub fn full_break_cost(counterspell: &CardData, ward: &CardData) -> Option<u32> {
+    let CardType::CounterSpell {
+        subtype,
+        strength,
+        pump,
+        break_subroutines,
+        ..
+    } = &counterspell.data
+    else {
+        return None;
+    };
+    let CardType::Ward {
+        subtype: ward_subtype,
+        strength: ward_strength,
+        subroutines,
+        ..
+    } = &ward.data
+    else {
+        return None;
+    };
+    if subtype.breaks() != *ward_subtype {
+        return None;
+    }
+
+    let pump_cost = if strength >= ward_strength {
+        0
+    } else {
+        let pump = pump.filter(|pump| pump.amount > 0)?;
+        (ward_strength - strength).div_ceil(pump.amount) * pump.cost.0
+    };
+    let break_cost = match subroutines.len() as u32 {
+        0 => 0,
+        _ if break_subroutines.amount == 0 => return None,
+        n => n.div_ceil(break_subroutines.amount) * break_subroutines.cost.0,
+    };
+    Some(pump_cost + break_cost)
+}
 
-    Ok(cards)
+/// One thing wrong with a card database. Positions are 1-based, like an editor's.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CardError {
+    Io(String),
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A misspelled faction, card type, subtype or effect.
+    UnknownVariant {
+        card: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// Anything else serde couldn't map onto `CardData`, such as a missing field.
+    Malformed {
+        card: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    IdMismatch {
+        key: String,
+        id: CardId,
+    },
+    DuplicateId(CardId),
+    MissingImage {
+        card: CardId,
+        path: String,
+    },
+}
+impl fmt::Display for CardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CardError::Io(message) => write!(f, "{}", message),
+            CardError::Syntax {
+                line,
+                column,
+                message,
+            } => write!(f, "line {} column {}: {}", line, column, message),
+            CardError::UnknownVariant {
+                card,
+                line,
+                column,
+                message,
+            }
+            | CardError::Malformed {
+                card,
+                line,
+                column,
+                message,
+            } => write!(f, "line {} column {}: {}: {}", line, column, card, message),
+            CardError::IdMismatch { key, id } => {
+                write!(f, "{} is listed under {}", id, key)
+            }
+            CardError::DuplicateId(id) => write!(f, "{} is defined more than once", id),
+            CardError::MissingImage { card, path } => {
+                write!(f, "{}: no image at {}", card, path)
+            }
+        }
+    }
+}
+
+/// Every problem found in a card database, so they can all be fixed in one go.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardErrors(pub Vec<CardError>);
+impl fmt::Display for CardErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for error in self.0.iter() {
+            writeln!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for CardErrors {}
+
+// The top level object as written, duplicate keys included. Each card is kept
+// as raw text so one bad card doesn't hide the problems of the others.
+struct RawCards<'a>(Vec<(String, &'a RawValue)>);
+impl<'de> Deserialize<'de> for RawCards<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = RawCards<'de>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an object of cards keyed by id")
+            }
+            fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Self::Value, M::Error> {
+                let mut entries = vec![];
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(RawCards(entries))
+            }
+        }
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+/// serde's message without the position it appends, which is reported separately.
+fn json_message(err: &serde_json::Error) -> String {
+    let message = err.to_string();
+    let suffix = format!(" at line {} column {}", err.line(), err.column());
+    message
+        .strip_suffix(&suffix)
+        .unwrap_or(&message)
+        .to_string()
+}
+
+/// Line and column of byte `offset` in `text`.
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = offset - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+fn parse_card(json: &str, key: &str, raw: &RawValue) -> Result<CardData, CardError> {
+    serde_json::from_str(raw.get()).map_err(|err| {
+        // serde reports where it stopped within the card, make that a place in the file.
+        let (start_line, start_column) =
+            position(json, raw.get().as_ptr() as usize - json.as_ptr() as usize);
+        let line = start_line + err.line() - 1;
+        let column = match err.line() {
+            1 => start_column + err.column() - 1,
+            _ => err.column(),
+        };
+        let card = key.to_string();
+        let message = json_message(&err);
+        match message.starts_with("unknown variant") {
+            true => CardError::UnknownVariant {
+                card,
+                line,
+                column,
+                message,
+            },
+            false => CardError::Malformed {
+                card,
+                line,
+                column,
+                message,
+            },
+        }
+    })
+}
+
+/// Cards under the key they were filed under in `cards.json`.
+pub type CardEntries = Vec<(String, CardData)>;
+
+/// Parses a card database as written, keys in file order and duplicates kept.
+/// Only unreadable files and broken JSON fail outright, a card that doesn't
+/// parse is left out and reported alongside the ones that did.
+pub fn read_cards(file_path: &str) -> Result<(CardEntries, Vec<CardError>), CardErrors> {
+    let json = fs::read_to_string(file_path)
+        .map_err(|err| CardErrors(vec![CardError::Io(format!("{}: {}", file_path, err))]))?;
+    parse_cards(&json)
+}
+
+fn parse_cards(json: &str) -> Result<(CardEntries, Vec<CardError>), CardErrors> {
+    let RawCards(entries) = serde_json::from_str(json).map_err(|err| {
+        CardErrors(vec![CardError::Syntax {
+            line: err.line(),
+            column: err.column(),
+            message: json_message(&err),
+        }])
+    })?;
+
+    let mut errors = vec![];
+    let mut cards = vec![];
+    for (key, raw) in entries {
+        match parse_card(json, &key, raw) {
+            Ok(card) => cards.push((key, card)),
+            Err(err) => errors.push(err),
+        }
+    }
+    Ok((cards, errors))
+}
+
+/// Reads a card database, checking every card rather than stopping at the first
+/// bad one. Images are the client's business and aren't looked at here.
+pub fn load_cards_from_json(file_path: &str) -> Result<HashMap<CardId, CardData>, CardErrors> {
+    let (cards, mut errors) = read_cards(file_path)?;
+    errors.extend(validate_cards(&cards));
+    if !errors.is_empty() {
+        return Err(CardErrors(errors));
+    }
+    Ok(cards.into_iter().collect())
+}
+
+/// Checks parsed cards against each other, returning every problem.
+pub fn validate_cards(cards: &[(String, CardData)]) -> Vec<CardError> {
+    let mut errors = vec![];
+    let mut seen = HashSet::new();
+    for (key, card) in cards {
+        if *key != card.id {
+            errors.push(CardError::IdMismatch {
+                key: key.clone(),
+                id: card.id.clone(),
+            });
+        }
+        // A repeated key would otherwise quietly replace the first card.
+        let ids: HashSet<&CardId> = HashSet::from([key, &card.id]);
+        for id in ids {
+            if !seen.insert(id.clone()) {
+                errors.push(CardError::DuplicateId(id.clone()));
+            }
+        }
+    }
+    errors
+}
+
+/// Cards whose image isn't there, resolved against the working directory.
+pub fn missing_images(cards: &[(String, CardData)]) -> Vec<CardError> {
+    cards
+        .iter()
+        .filter(|(_, card)| !Path::new(&card.image_file).exists())
+        .map(|(_, card)| CardError::MissingImage {
+            card: card.id.clone(),
+            path: card.image_file.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A card database with one event per `(key, id, faction)`, each
+    /// spread over eight lines the way `cards.json` is written.
+    fn database(cards: &[(&str, &str, &str)]) -> String {
+        let cards: Vec<String> = cards
+            .iter()
+            .map(|(key, id, faction)| {
+                format!(
+                    r#"  "{key}": {{
+    "id": "{id}",
+    "title": "Test",
+    "faction": "{faction}",
+    "text": "",
+    "image_file": "Cargo.toml",
+    "data": {{ "Event": {{ "cost": 0 }} }}
+  }}"#
+                )
+            })
+            .collect();
+        format!("{{\n{}\n}}", cards.join(",\n"))
+    }
+
+    #[test]
+    fn positions_are_one_based() {
+        let text = "ab\ncde\n";
+        assert_eq!(position(text, 0), (1, 1));
+        assert_eq!(position(text, 1), (1, 2));
+        assert_eq!(position(text, 3), (2, 1));
+        assert_eq!(position(text, 5), (2, 3));
+        assert_eq!(position(text, 7), (3, 1));
+    }
+
+    #[test]
+    fn card_errors_point_into_the_file() {
+        let json = database(&[("a", "a", "Thief"), ("b", "b", "Thieves")]);
+        let (cards, errors) = parse_cards(&json).unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(errors.len(), 1);
+        let CardError::UnknownVariant {
+            card, line, column, ..
+        } = &errors[0]
+        else {
+            panic!("{:?}", errors[0]);
+        };
+        assert_eq!(card, "b");
+        // Card "b" opens on line 10 and its faction is three lines down, serde
+        // stops at the closing quote of the name.
+        assert_eq!((*line, *column), (13, 24));
+    }
+
+    #[test]
+    fn columns_on_a_card_that_starts_mid_line_are_offset() {
+        let json = r#"{ "a": { "id": 1 } }"#;
+        let (_, errors) = parse_cards(json).unwrap();
+        let CardError::Malformed { line, column, .. } = &errors[0] else {
+            panic!("{:?}", errors[0]);
+        };
+        assert_eq!((*line, *column), (1, 16));
+    }
+
+    #[test]
+    fn broken_json_fails_outright() {
+        let json = "{\n  \"a\": {\n}";
+        let CardErrors(errors) = parse_cards(json).unwrap_err();
+        assert!(matches!(errors[..], [CardError::Syntax { line: 3, .. }]));
+    }
+
+    #[test]
+    fn duplicate_keys_are_kept_and_reported() {
+        let json = database(&[("a", "a", "Thief"), ("a", "a", "Sanctum")]);
+        let (cards, errors) = parse_cards(&json).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(cards.len(), 2);
+        assert_eq!(
+            validate_cards(&cards),
+            vec![CardError::DuplicateId("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn ids_must_match_their_keys() {
+        let json = database(&[("a", "b", "Thief"), ("b", "c", "Thief")]);
+        let (cards, _) = parse_cards(&json).unwrap();
+        assert_eq!(
+            validate_cards(&cards),
+            vec![
+                CardError::IdMismatch {
+                    key: "a".to_string(),
+                    id: "b".to_string(),
+                },
+                CardError::IdMismatch {
+                    key: "b".to_string(),
+                    id: "c".to_string(),
+                },
+                // The second card's key was already taken by the first card's id.
+                CardError::DuplicateId("b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_images_are_reported() {
+        let json = database(&[("a", "a", "Thief")]).replace("Cargo.toml", "no-such-image.png");
+        let (cards, _) = parse_cards(&json).unwrap();
+        assert_eq!(validate_cards(&cards), vec![]);
+        assert_eq!(
+            missing_images(&cards),
+            vec![CardError::MissingImage {
+                card: "a".to_string(),
+                path: "no-such-image.png".to_string(),
+            }]
+        );
+    }
 }
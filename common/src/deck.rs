@@ -0,0 +1,22 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::CardId;
+
+// Card id -> number of copies, sorted so the same list always builds the same deck.
+pub type DeckList = BTreeMap<CardId, u32>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DeckChoice {
+    Named(String), // A deck file in the server's decks directory
+    List(DeckList),
+}
+
+pub fn load_deck_from_json(file_path: &str) -> Result<DeckList, Box<dyn std::error::Error>> {
+    let json_string = fs::read_to_string(file_path)?;
+    let deck: DeckList = serde_json::from_str(&json_string)?;
+
+    Ok(deck)
+}
@@ -1,13 +1,23 @@
+pub mod args;
 pub mod card;
+pub mod deck;
 pub mod player;
+pub mod replay;
+pub mod rules;
+pub mod versioned;
 use std::collections::HashMap;
+use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
 pub type InstanceId = u32;
 pub type CardId = String;
+/// Issued when joining a table, sent back with `ActionReq::Resume` to take the
+/// seat back after a dropped connection.
+pub type SessionToken = String;
 
-use crate::card::{CardData, CardState, Faction};
+use crate::card::{CardData, CardState, Counter, Counters, Faction};
+use crate::deck::DeckChoice;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum PlayerType {
@@ -20,13 +30,13 @@ pub struct BasicStats {
     pub stamina: u32,
     pub score: u32,
 }
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Remote {
     pub wards: Vec<InstanceId>,
     pub contents: Option<InstanceId>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ThiefStateInternal {
     pub stats: BasicStats,
 
@@ -40,7 +50,7 @@ pub struct ThiefStateInternal {
     pub ally_slots: Vec<InstanceId>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SanctumStateInternal {
     pub stats: BasicStats,
 
@@ -54,6 +64,88 @@ pub struct SanctumStateInternal {
     pub discard_lair: Vec<InstanceId>, // Protects Archives
     pub remotes: Vec<Remote>,
 }
+impl SanctumStateInternal {
+    /// Every card on the Sanctum's servers, wards and remote contents alike.
+    pub fn installed(&self) -> impl Iterator<Item = InstanceId> + '_ {
+        self.hand_lair
+            .iter()
+            .chain(self.deck_lair.iter())
+            .chain(self.discard_lair.iter())
+            .chain(
+                self.remotes
+                    .iter()
+                    .flat_map(|remote| remote.wards.iter().chain(remote.contents.iter())),
+            )
+            .copied()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ServerTarget {
+    HandLair,
+    DeckLair,
+    DiscardLair,
+    Remote(usize),
+}
+
+/// Where an Infiltration currently stands. Ward positions index into the
+/// target's wards, 0 being the innermost one.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum InfiltrationStep {
+    ApproachWard(usize), // Thief may continue or jack out
+    RezWindow(usize),    // Sanctum may rez the approached ward
+    EncounterWard(usize),
+    ApproachServer,
+    Access,
+}
+
+/// A response produced by the game. Private updates carry a second, redacted
+/// view for everyone who isn't the owner of the hidden information.
+#[allow(clippy::large_enum_variant)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Update {
+    Public(Response),
+    Private {
+        owner: Faction,
+        owner_view: Response,
+        other_view: Response,
+    },
+}
+impl Update {
+    pub fn view_for(&self, faction: Faction) -> &Response {
+        match self {
+            Update::Public(response) => response,
+            Update::Private {
+                owner,
+                owner_view,
+                other_view,
+            } => {
+                if *owner == faction {
+                    owner_view
+                } else {
+                    other_view
+                }
+            }
+        }
+    }
+    /// Omniscient spectators see the owner's side of private updates.
+    pub fn spectator_view(&self, omniscient: bool) -> &Response {
+        match self {
+            Update::Public(response) => response,
+            Update::Private {
+                owner_view,
+                other_view,
+                ..
+            } => {
+                if omniscient {
+                    owner_view
+                } else {
+                    other_view
+                }
+            }
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RemoteRes {
@@ -64,8 +156,9 @@ pub struct RemoteRes {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CommonState {
     pub stats: BasicStats,
-    pub deck: Vec<CardState>,
-    pub hand: Vec<CardState>,
+    pub deck_size: u32,
+    pub hand_size: u32,
+    pub hand: Vec<CardState>, // Empty unless it's your own hand
     pub discard: Vec<CardState>,
     pub score_area: Vec<CardState>,
 }
@@ -81,13 +174,111 @@ pub struct SanctumState {
     pub deck_lair: Option<Vec<CardState>>,
     pub discard_lair: Option<Vec<CardState>>,
     pub remotes: Option<Vec<RemoteRes>>,
+    pub rezzed: Vec<InstanceId>, // The owner sees every installed card's face, rezzed or not
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum GameOverReason {
+    ArtifactPoints,
+    DeckOut,  // Sanctum couldn't make its mandatory draw
+    Flatline, // Thief took more Backlash than cards in hand
+    Forfeit,  // The loser didn't come back before their seat expired
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Response {
     Initial(InitStateResponse),
     DrawCard {
-        card:CardState},
+        faction: Faction,
+        card: Option<CardState>, // Only the player who drew sees the card
+    },
+    StatsChanged {
+        faction: Faction,
+        stats: BasicStats,
+    },
+    TurnStarted {
+        turn: Faction,
+        turn_number: u32,
+    },
+    Infiltration {
+        target: ServerTarget,
+        step: InfiltrationStep,
+    },
+    InfiltrationEnded {
+        target: ServerTarget,
+        successful: bool,
+    },
+    Reveal {
+        card: CardState,
+    },
+    Access {
+        card: CardState,
+    },
+    StrengthChanged {
+        card: InstanceId,
+        strength: u32,
+    },
+    SubroutineBroken {
+        ward: InstanceId,
+        subroutine: usize,
+    },
+    SubroutineFired {
+        ward: InstanceId,
+        subroutine: usize,
+    },
+    Discarded {
+        faction: Faction,
+        card: CardState,
+        from_hand: Option<InstanceId>, // Id the owner knew the card by in their hand
+    },
+    CountersChanged {
+        card: InstanceId,
+        counters: Counters,
+    },
+    Installed {
+        card: CardState, // Hidden from the opponent until rezzed
+        server: ServerTarget,
+        ward: bool, // Protecting the server rather than in it, public either way
+        from_hand: Option<InstanceId>, // Id the owner knew the card by in their hand
+    },
+    Played {
+        faction: Faction,
+        card: CardState, // Goes to the discard once its effects resolve
+        from_hand: Option<InstanceId>, // Id the owner knew the card by in their hand
+    },
+    RigInstalled {
+        card: CardState,
+        from_hand: Option<InstanceId>, // Id the owner knew the card by in their hand
+    },
+    Scored {
+        faction: Faction,
+        card: CardState,
+        from: InstanceId, // Id it had installed or when accessed, public either way
+    },
+    GameOver {
+        winner: Faction,
+        reason: GameOverReason,
+    },
+    InitRejected {
+        reason: String,
+    },
+    ActionRejected {
+        reason: ActionError,
+    },
+    Tables {
+        tables: Vec<TableInfo>,
+    },
+    LobbyRejected {
+        reason: String,
+    },
+    Joined {
+        token: SessionToken,
+    },
+    PlayerConnection {
+        faction: Faction,
+        connected: bool,
+    },
 }
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum PlayerStateResponse {
@@ -101,8 +292,20 @@ pub enum PlayerStateResponse {
     },
 }
 impl PlayerStateResponse {
+    pub fn faction(&self) -> Faction {
+        match self {
+            PlayerStateResponse::Thief { .. } => Faction::Thief,
+            PlayerStateResponse::Sanctum { .. } => Faction::Sanctum,
+        }
+    }
     pub fn get_common(&self) -> &CommonState {
-        match (self) {
+        match self {
+            PlayerStateResponse::Thief { common, .. }
+            | PlayerStateResponse::Sanctum { common, .. } => common,
+        }
+    }
+    pub fn get_common_mut(&mut self) -> &mut CommonState {
+        match self {
             PlayerStateResponse::Thief { common, .. }
             | PlayerStateResponse::Sanctum { common, .. } => common,
         }
@@ -114,14 +317,141 @@ pub struct InitStateResponse {
     pub other_state: Option<PlayerStateResponse>,
     pub card_set: HashMap<CardId, CardData>,
     pub turn: Faction,
+    pub turn_number: u32,
+    pub infiltration: Option<(ServerTarget, InfiltrationStep)>,
+    pub game_over: Option<(Faction, GameOverReason)>,
 }
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ActionError {
+    NotYourTurn,
+    WrongFaction,
+    NotEnoughStamina,
+    NotEnoughMana,
+    UnknownCard,
+    NotYourCard,
+    EmptyDeck,
+    InfiltrationInProgress,
+    NotInfiltrating,
+    UnknownServer,
+    CannotRez,
+    CardNotInstalled,
+    CannotBreak,
+    InsufficientStrength,
+    InvalidSubroutine,
+    NotAnArtifact,
+    AttunementIncomplete,
+    NotInHand,
+    CannotInstall,
+    ServerOccupied,
+    NotEnoughFocus,
+    CannotPlay,
+    NoCounter,
+    NoCounterAbility,
+    MalformedRequest,
+    NotJoined,
+    Spectating,
+    GameNotStarted,
+    GameOver,
+    UnsupportedAction,
+}
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionError::NotYourTurn => write!(f, "it is not your turn"),
+            ActionError::WrongFaction => write!(f, "your faction can't do that"),
+            ActionError::NotEnoughStamina => write!(f, "not enough stamina"),
+            ActionError::NotEnoughMana => write!(f, "not enough mana"),
+            ActionError::UnknownCard => write!(f, "no such card"),
+            ActionError::NotYourCard => write!(f, "that card isn't yours"),
+            ActionError::EmptyDeck => write!(f, "deck is empty"),
+            ActionError::InfiltrationInProgress => write!(f, "an infiltration is in progress"),
+            ActionError::NotInfiltrating => write!(f, "there is no infiltration in progress"),
+            ActionError::UnknownServer => write!(f, "no such server"),
+            ActionError::CannotRez => write!(f, "that card can't be rezzed now"),
+            ActionError::CardNotInstalled => write!(f, "card is not installed"),
+            ActionError::CannotBreak => write!(f, "that counterspell can't break this ward"),
+            ActionError::InsufficientStrength => write!(f, "counterspell strength is too low"),
+            ActionError::InvalidSubroutine => write!(f, "invalid subroutine"),
+            ActionError::NotAnArtifact => write!(f, "card is not an ancient artifact"),
+            ActionError::AttunementIncomplete => write!(f, "artifact is not fully attuned"),
+            ActionError::NotInHand => write!(f, "card is not in your hand"),
+            ActionError::CannotInstall => write!(f, "that card can't be installed there"),
+            ActionError::ServerOccupied => write!(f, "that remote already holds a card"),
+            ActionError::NotEnoughFocus => write!(f, "not enough focus for that counterspell"),
+            ActionError::CannotPlay => write!(f, "only events and operations can be played"),
+            ActionError::NoCounter => write!(f, "no such counter to spend on that card"),
+            ActionError::NoCounterAbility => {
+                write!(f, "that card has nothing to spend that counter on")
+            }
+            ActionError::MalformedRequest => write!(f, "couldn't read that request"),
+            ActionError::NotJoined => write!(f, "join the game first"),
+            ActionError::Spectating => write!(f, "spectators can't play"),
+            ActionError::GameNotStarted => write!(f, "the game hasn't started yet"),
+            ActionError::GameOver => write!(f, "the game is over"),
+            ActionError::UnsupportedAction => write!(f, "action is not supported here"),
+        }
+    }
+}
+
+/// A table as shown in the lobby, with the names of the seated players.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableInfo {
+    pub name: String,
+    pub sanctum: Option<String>,
+    pub thief: Option<String>,
+    pub started: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct InitReq {
+    pub table: String,
     pub name: String,
     pub faction: Faction,
+    pub deck: DeckChoice,
 }
-#[derive(Serialize, Deserialize, Debug)]
+impl InitReq {
+    /// What to send, in order, to sit down at the table. Whoever comes first
+    /// opens it, the second player just gets told it exists.
+    pub fn join_requests(self) -> [ActionReq; 2] {
+        [ActionReq::CreateTable(self.table.clone()), ActionReq::Init(self)]
+    }
+}
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum ActionReq {
-    DrawCard(InstanceId),
+    DrawCard,
+    GainMana,
+    Infiltrate(ServerTarget),
+    Rez(InstanceId),
+    Continue,
+    JackOut,
+    PumpStrength(InstanceId),
+    BreakSubroutines {
+        counterspell: InstanceId,
+        subroutines: Vec<usize>,
+    },
+    Attune(InstanceId),
+    ScoreArtifact(InstanceId),
+    /// Installs a card from the Sanctum's hand face-down. Wards protect any
+    /// server, Assets and Ancient Artifacts go into a remote. `Remote(n)` with
+    /// `n` one past the last remote opens a new one.
+    Install {
+        card: InstanceId,
+        server: ServerTarget,
+    },
+    /// Spends one counter from a rezzed card or an installed artifact, resolving
+    /// the card's ability for that kind of counter. Cards without one refuse it.
+    SpendCounter {
+        card: InstanceId,
+        counter: Counter,
+    },
+    /// Plays an Event or Operation from hand, paying its cost and resolving its effects.
+    PlayCard(InstanceId),
+    /// Installs a counterspell, gear or ally from the Thief's hand face-up into
+    /// its slot, paying its cost.
+    InstallRig(InstanceId),
     Init(InitReq),
+    ListTables,
+    CreateTable(String),
+    Resume(SessionToken),
+    Spectate(String),
 }
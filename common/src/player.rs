@@ -1,7 +1,9 @@
-use crate::card::{CardData, Faction, Mana};
+use crate::card::Faction;
 // In your game_logic.rs or model.rs
 
+/// A connection to the server. Spectators have a table but no faction.
 pub struct Player {
     pub id: Option<String>,
     pub faction: Option<Faction>,
+    pub table: Option<String>,
 }
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::card::Faction;
+use crate::deck::DeckList;
+use crate::versioned::{VersionedError, parse_versioned};
+use crate::{ActionReq, InitStateResponse, Update};
+
+// Bumped whenever the layout below changes, old files are refused rather than misread.
+pub const REPLAY_VERSION: u32 = 4;
+
+/// First line of a replay file. With the seed and both decks the server can
+/// rebuild the game, `initial` is the omniscient starting state for viewers.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub table: String,
+    pub seed: u64,
+    pub sanctum_deck: DeckList,
+    pub thief_deck: DeckList,
+    pub initial: InitStateResponse,
+}
+
+/// Every line after the header, in the order the server accepted them.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum GameEvent {
+    Action {
+        faction: Faction,
+        action: ActionReq,
+        updates: Vec<Update>,
+    },
+    // A player didn't come back before their seat expired.
+    Forfeit {
+        loser: Faction,
+        updates: Vec<Update>,
+    },
+}
+impl GameEvent {
+    pub fn updates(&self) -> &[Update] {
+        match self {
+            GameEvent::Action { updates, .. } | GameEvent::Forfeit { updates, .. } => updates,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayFile {
+    pub header: ReplayHeader,
+    pub events: Vec<GameEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    Io(String),
+    Json { line: usize, message: String },
+    Empty,
+    UnsupportedVersion(u32),
+}
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(message) => write!(f, "{}", message),
+            ReplayError::Json { line, message } => write!(f, "line {}: {}", line, message),
+            ReplayError::Empty => write!(f, "the replay has no header"),
+            ReplayError::UnsupportedVersion(version) => write!(
+                f,
+                "replay version {} isn't supported, expected {}",
+                version, REPLAY_VERSION
+            ),
+        }
+    }
+}
+impl std::error::Error for ReplayError {}
+
+/// Reads a replay written as JSON lines, a header followed by one event per line.
+pub fn load_replay(file_path: &str) -> Result<ReplayFile, ReplayError> {
+    let contents = fs::read_to_string(file_path).map_err(|err| ReplayError::Io(err.to_string()))?;
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let parse_error = |line: usize, err: serde_json::Error| ReplayError::Json {
+        line: line + 1,
+        message: err.to_string(),
+    };
+
+    let (number, line) = lines.next().ok_or(ReplayError::Empty)?;
+    let header: ReplayHeader =
+        parse_versioned(line, REPLAY_VERSION).map_err(|err| match err {
+            VersionedError::Json(err) => parse_error(number, err),
+            VersionedError::UnsupportedVersion(version) => ReplayError::UnsupportedVersion(version),
+        })?;
+    let events = lines
+        .map(|(number, line)| serde_json::from_str(line).map_err(|err| parse_error(number, err)))
+        .collect::<Result<Vec<GameEvent>, _>>()?;
+    Ok(ReplayFile { header, events })
+}
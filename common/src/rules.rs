@@ -0,0 +1,12 @@
+// The numbers the rules are built on. The server enforces them and bots plan
+// with them, so they don't live in the rules engine.
+pub const STARTING_MANA: u32 = 5;
+pub const SANCTUM_STAMINA: u32 = 3;
+pub const THIEF_STAMINA: u32 = 4;
+pub const ARTIFACT_POINTS_TO_WIN: u32 = 7;
+/// Mana paid alongside one stamina for each attunement counter.
+pub const ATTUNE_COST: u32 = 1;
+/// Mana paid for each ward already protecting the server a new ward goes on.
+pub const WARD_TAX: u32 = 1;
+/// Total `focus_cost` of the counterspells the Thief can have installed at once.
+pub const FOCUS_CAPACITY: u32 = 4;
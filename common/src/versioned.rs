@@ -0,0 +1,27 @@
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+/// Why `parse_versioned` couldn't read a file.
+#[derive(Debug)]
+pub enum VersionedError {
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+/// Parses JSON that carries a top-level `version` field, refusing any version
+/// other than `expected` rather than misreading it. The version is checked on
+/// its own first, as a newer layout might not parse at all.
+pub fn parse_versioned<T: DeserializeOwned>(
+    json: &str,
+    expected: u32,
+) -> Result<T, VersionedError> {
+    #[derive(Deserialize)]
+    struct Version {
+        version: u32,
+    }
+    let Version { version } = serde_json::from_str(json).map_err(VersionedError::Json)?;
+    if version != expected {
+        return Err(VersionedError::UnsupportedVersion(version));
+    }
+    serde_json::from_str(json).map_err(VersionedError::Json)
+}
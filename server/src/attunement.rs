@@ -0,0 +1,267 @@
+use common::card::{CardType, Counter, Faction};
+use common::rules::{ARTIFACT_POINTS_TO_WIN, ATTUNE_COST};
+use common::{GameOverReason, InstanceId, Response};
+
+use crate::game_state::{ActionError, GameState, Update};
+use crate::instances::InstantiateLocation;
+
+impl GameState {
+    fn artifact_stats(&self, instance_id: InstanceId) -> Result<(u32, u32), ActionError> {
+        match self.card_data(instance_id).data {
+            CardType::AncientArtifact { vp, attunement } => Ok((vp, attunement)),
+            _ => Err(ActionError::NotAnArtifact),
+        }
+    }
+
+    fn is_in_remote(&self, instance_id: InstanceId) -> bool {
+        self.sanctum
+            .remotes
+            .iter()
+            .any(|remote| remote.contents == Some(instance_id))
+    }
+
+    /// Places one attunement counter on an artifact installed in a remote, then
+    /// resolves whatever the artifact does when attuned.
+    pub(crate) fn attune(
+        &mut self,
+        faction: Faction,
+        instance_id: InstanceId,
+    ) -> Result<Vec<Update>, ActionError> {
+        if faction != Faction::Sanctum {
+            return Err(ActionError::WrongFaction);
+        }
+        if !self.is_in_remote(instance_id) {
+            return Err(ActionError::CardNotInstalled);
+        }
+        self.artifact_stats(instance_id)?;
+        self.pay_mana(Faction::Sanctum, ATTUNE_COST)?;
+
+        let mut updates = vec![self.add_counters(instance_id, Counter::Attunement, 1)];
+        let effects = self.card_data(instance_id).effects.clone();
+        updates.extend(self.resolve_effects(Faction::Sanctum, instance_id, &effects));
+        Ok(updates)
+    }
+
+    pub(crate) fn score_artifact(
+        &mut self,
+        faction: Faction,
+        instance_id: InstanceId,
+    ) -> Result<Vec<Update>, ActionError> {
+        if faction != Faction::Sanctum {
+            return Err(ActionError::WrongFaction);
+        }
+        if !self.is_in_remote(instance_id) {
+            return Err(ActionError::CardNotInstalled);
+        }
+        let (_, attunement) = self.artifact_stats(instance_id)?;
+        if self.counter(instance_id, Counter::Attunement) < attunement {
+            return Err(ActionError::AttunementIncomplete);
+        }
+        Ok(self.add_to_score_area(Faction::Sanctum, instance_id))
+    }
+
+    /// Accessed artifacts are stolen by the Thief, wherever they were.
+    pub(crate) fn steal_artifact(&mut self, instance_id: InstanceId) -> Vec<Update> {
+        if self.artifact_stats(instance_id).is_err() {
+            return vec![];
+        }
+        self.add_to_score_area(Faction::Thief, instance_id)
+    }
+
+    fn add_to_score_area(&mut self, faction: Faction, instance_id: InstanceId) -> Vec<Update> {
+        let (vp, _) = self.artifact_stats(instance_id).unwrap();
+
+        self.sanctum.hand.retain(|&id| id != instance_id);
+        self.sanctum.deck.retain(|&id| id != instance_id);
+        self.sanctum.discard.retain(|&id| id != instance_id);
+        for remote in self.sanctum.remotes.iter_mut() {
+            if remote.contents == Some(instance_id) {
+                remote.contents = None;
+            }
+        }
+        let from = instance_id;
+        let instance_id =
+            self.instances
+                .move_to(instance_id, InstantiateLocation::ScoreArea, &mut self.rng);
+        match faction {
+            Faction::Sanctum => self.sanctum.score_area.push(instance_id),
+            Faction::Thief => self.thief.score_area.push(instance_id),
+        }
+        self.stats_mut(faction).score += vp;
+
+        let mut updates = vec![
+            Update::Public(Response::Scored {
+                faction,
+                card: self.instances.create_card_state(instance_id, true),
+                from,
+            }),
+            self.stats_update(faction),
+        ];
+        if self.stats(faction).score >= ARTIFACT_POINTS_TO_WIN {
+            updates.push(self.end_game(faction, GameOverReason::ArtifactPoints));
+        }
+        updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::card::{AssetSubType, Mana};
+    use common::rules::{SANCTUM_STAMINA, STARTING_MANA};
+    use common::{ActionReq, Remote, ServerTarget};
+
+    use super::*;
+    use crate::testing::{card, game, instance, turn};
+
+    /// The Sanctum's turn, with a two point artifact needing two attunements
+    /// installed in the first remote.
+    fn artifact_game() -> (GameState, InstanceId) {
+        let mut game = game(vec![
+            card(
+                "artifact",
+                Faction::Sanctum,
+                CardType::AncientArtifact {
+                    vp: 2,
+                    attunement: 2,
+                },
+            ),
+            card(
+                "asset",
+                Faction::Sanctum,
+                CardType::Asset {
+                    subtype: AssetSubType::Ritual,
+                    cost: Mana(0),
+                },
+            ),
+            card("event", Faction::Thief, CardType::Event { cost: Mana(0) }),
+        ]);
+        let artifact = instance(&mut game, "artifact", InstantiateLocation::Board(true));
+        game.sanctum.remotes.push(Remote {
+            wards: vec![],
+            contents: Some(artifact),
+        });
+        (game, artifact)
+    }
+
+    #[test]
+    fn attuned_artifacts_are_scored() {
+        let (mut game, artifact) = artifact_game();
+        game.handle_action(Faction::Sanctum, &ActionReq::Attune(artifact))
+            .unwrap();
+        assert_eq!(game.counter(artifact, Counter::Attunement), 1);
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA - ATTUNE_COST);
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::ScoreArtifact(artifact))
+                .err(),
+            Some(ActionError::AttunementIncomplete)
+        );
+
+        game.handle_action(Faction::Sanctum, &ActionReq::Attune(artifact))
+            .unwrap();
+        let updates = game
+            .handle_action(Faction::Sanctum, &ActionReq::ScoreArtifact(artifact))
+            .unwrap();
+        assert!(updates.iter().any(|update| matches!(update,
+            Update::Public(Response::Scored { faction: Faction::Sanctum, from, .. })
+                if *from == artifact)));
+        assert_eq!(game.sanctum.stats.score, 2);
+        assert_eq!(game.sanctum.score_area.len(), 1);
+        assert_eq!(game.sanctum.remotes[0].contents, None);
+        // Scoring is free, only the attunements took stamina.
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA - 2);
+    }
+
+    #[test]
+    fn only_installed_artifacts_are_attuned() {
+        let (mut game, _) = artifact_game();
+        let in_deck = *game.sanctum.deck.last().unwrap();
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::Attune(in_deck))
+                .err(),
+            Some(ActionError::CardNotInstalled)
+        );
+        let asset = instance(&mut game, "asset", InstantiateLocation::Board(true));
+        game.sanctum.remotes.push(Remote {
+            wards: vec![],
+            contents: Some(asset),
+        });
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::Attune(asset))
+                .err(),
+            Some(ActionError::NotAnArtifact)
+        );
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA);
+    }
+
+    #[test]
+    fn attuning_needs_mana() {
+        let (mut game, artifact) = artifact_game();
+        game.sanctum.stats.mana_pool = 0;
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::Attune(artifact))
+                .err(),
+            Some(ActionError::NotEnoughMana)
+        );
+        assert_eq!(game.counter(artifact, Counter::Attunement), 0);
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA);
+    }
+
+    #[test]
+    fn accessed_artifacts_are_stolen() {
+        let (mut game, artifact) = artifact_game();
+        turn(&mut game, Faction::Thief);
+        game.handle_action(
+            Faction::Thief,
+            &ActionReq::Infiltrate(ServerTarget::Remote(0)),
+        )
+        .unwrap();
+        let updates = game
+            .handle_action(Faction::Thief, &ActionReq::Continue)
+            .unwrap();
+        assert!(updates.iter().any(|update| matches!(update,
+            Update::Public(Response::Scored { faction: Faction::Thief, from, .. })
+                if *from == artifact)));
+        assert_eq!(game.thief.stats.score, 2);
+        assert_eq!(game.thief.score_area.len(), 1);
+        assert_eq!(game.sanctum.remotes[0].contents, None);
+        // The Thief can't attune, let alone score for the Sanctum.
+        let stolen = game.thief.score_area[0];
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::Attune(stolen))
+                .err(),
+            Some(ActionError::NotYourCard)
+        );
+    }
+
+    #[test]
+    fn reaching_the_artifact_points_wins() {
+        let (mut game, artifact) = artifact_game();
+        game.sanctum.stats.score = ARTIFACT_POINTS_TO_WIN - 2;
+        game.instances
+            .get_mut_instantiated_card(artifact)
+            .counters
+            .insert(Counter::Attunement, 2);
+        game.handle_action(Faction::Sanctum, &ActionReq::ScoreArtifact(artifact))
+            .unwrap();
+        assert_eq!(
+            game.outcome,
+            Some((Faction::Sanctum, GameOverReason::ArtifactPoints))
+        );
+
+        let (mut game, _) = artifact_game();
+        game.thief.stats.score = ARTIFACT_POINTS_TO_WIN - 1;
+        turn(&mut game, Faction::Thief);
+        game.handle_action(
+            Faction::Thief,
+            &ActionReq::Infiltrate(ServerTarget::Remote(0)),
+        )
+        .unwrap();
+        game.handle_action(Faction::Thief, &ActionReq::Continue)
+            .unwrap();
+        assert_eq!(
+            game.outcome,
+            Some((Faction::Thief, GameOverReason::ArtifactPoints))
+        );
+        assert!(game.infiltration.is_none());
+    }
+}
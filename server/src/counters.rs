@@ -0,0 +1,212 @@
+use common::card::{CardType, Counter, Faction};
+use common::{InstanceId, Response};
+
+use crate::game_state::{ActionError, GameState, Update};
+use crate::instances::InstantiateLocation;
+
+impl GameState {
+    pub fn counter(&self, instance_id: InstanceId, counter: Counter) -> u32 {
+        self.instances
+            .get_instantiated_card(instance_id)
+            .counters
+            .get(&counter)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn counters_update(&self, instance_id: InstanceId) -> Update {
+        Update::Public(Response::CountersChanged {
+            card: instance_id,
+            counters: self
+                .instances
+                .get_instantiated_card(instance_id)
+                .counters
+                .clone(),
+        })
+    }
+
+    /// Counters only ever come from attuning and card effects. Like advancing,
+    /// placing them is something a card does rather than an action of its own.
+    pub(crate) fn add_counters(
+        &mut self,
+        instance_id: InstanceId,
+        counter: Counter,
+        amount: u32,
+    ) -> Update {
+        let card = self.instances.get_mut_instantiated_card(instance_id);
+        *card.counters.entry(counter).or_default() += amount;
+        self.counters_update(instance_id)
+    }
+
+    /// Takes one counter off a rezzed card, or an installed artifact, and resolves
+    /// the card's ability for that kind of counter. The owner can do this
+    /// whenever they have priority.
+    pub(crate) fn spend_counter(
+        &mut self,
+        faction: Faction,
+        instance_id: InstanceId,
+        counter: Counter,
+    ) -> Result<Vec<Update>, ActionError> {
+        let data = self.card_data(instance_id);
+        let usable = match self.instances.get_instantiated_card(instance_id).location {
+            InstantiateLocation::Board(false) => true,
+            // Artifacts are never rezzed, their counters are used face-down.
+            InstantiateLocation::Board(true) => {
+                matches!(data.data, CardType::AncientArtifact { .. })
+            }
+            _ => false,
+        };
+        if !usable {
+            return Err(ActionError::CardNotInstalled);
+        }
+        if self.counter(instance_id, counter) == 0 {
+            return Err(ActionError::NoCounter);
+        }
+        let effects = data
+            .counter_abilities
+            .iter()
+            .find(|ability| ability.counter == counter)
+            .map(|ability| ability.effects.clone())
+            .ok_or(ActionError::NoCounterAbility)?;
+
+        let counters = &mut self
+            .instances
+            .get_mut_instantiated_card(instance_id)
+            .counters;
+        match counters.get_mut(&counter) {
+            Some(1) => {
+                counters.remove(&counter);
+            }
+            Some(count) => *count -= 1,
+            None => unreachable!(),
+        }
+        let mut updates = vec![self.counters_update(instance_id)];
+        updates.extend(self.resolve_effects(faction, instance_id, &effects));
+        Ok(updates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::card::{AssetSubType, CounterAbility, Effect, Mana};
+    use common::rules::{SANCTUM_STAMINA, STARTING_MANA};
+    use common::{ActionReq, Remote};
+
+    use super::*;
+    use crate::testing::{card, game, in_hand, instance};
+
+    /// Both cards give 2 mana for each power counter spent off them, the
+    /// artifact places one on itself whenever it's attuned.
+    fn counter_game() -> GameState {
+        let power = vec![CounterAbility {
+            counter: Counter::Power,
+            effects: vec![Effect::GainMana(2)],
+        }];
+        let mut asset = card(
+            "asset",
+            Faction::Sanctum,
+            CardType::Asset {
+                subtype: AssetSubType::Ritual,
+                cost: Mana(0),
+            },
+        );
+        asset.counter_abilities = power.clone();
+        let mut artifact = card(
+            "artifact",
+            Faction::Sanctum,
+            CardType::AncientArtifact {
+                vp: 2,
+                attunement: 3,
+            },
+        );
+        artifact.effects = vec![Effect::PlaceCounter(Counter::Power, 1)];
+        artifact.counter_abilities = power;
+        game(vec![
+            asset,
+            artifact,
+            card("event", Faction::Thief, CardType::Event { cost: Mana(0) }),
+        ])
+    }
+
+    fn installed(game: &mut GameState, card: &str, hidden: bool) -> InstanceId {
+        let instance_id = instance(game, card, InstantiateLocation::Board(hidden));
+        game.sanctum.remotes.push(Remote {
+            wards: vec![],
+            contents: Some(instance_id),
+        });
+        instance_id
+    }
+
+    fn spend(
+        game: &mut GameState,
+        card: InstanceId,
+        counter: Counter,
+    ) -> Result<Vec<Update>, ActionError> {
+        game.handle_action(Faction::Sanctum, &ActionReq::SpendCounter { card, counter })
+    }
+
+    #[test]
+    fn spending_a_counter_resolves_its_ability_for_free() {
+        let mut game = counter_game();
+        let asset = installed(&mut game, "asset", false);
+        game.add_counters(asset, Counter::Power, 2);
+        spend(&mut game, asset, Counter::Power).unwrap();
+        assert_eq!(game.counter(asset, Counter::Power), 1);
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA + 2);
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA);
+
+        spend(&mut game, asset, Counter::Power).unwrap();
+        // Kinds with no counters left aren't kept around at zero.
+        assert!(
+            game.instances
+                .get_instantiated_card(asset)
+                .counters
+                .is_empty()
+        );
+        assert_eq!(
+            spend(&mut game, asset, Counter::Power).err(),
+            Some(ActionError::NoCounter)
+        );
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA + 4);
+    }
+
+    #[test]
+    fn artifacts_spend_the_counters_they_place_face_down() {
+        let mut game = counter_game();
+        let artifact = installed(&mut game, "artifact", true);
+        game.handle_action(Faction::Sanctum, &ActionReq::Attune(artifact))
+            .unwrap();
+        assert_eq!(game.counter(artifact, Counter::Attunement), 1);
+        assert_eq!(game.counter(artifact, Counter::Power), 1);
+        spend(&mut game, artifact, Counter::Power).unwrap();
+        assert_eq!(game.counter(artifact, Counter::Power), 0);
+        // Attuning cost one mana, the power counter gave two back.
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA + 1);
+    }
+
+    #[test]
+    fn counters_are_only_spent_on_an_ability_of_a_card_in_play() {
+        let mut game = counter_game();
+        let face_down = installed(&mut game, "asset", true);
+        game.add_counters(face_down, Counter::Power, 1);
+        assert_eq!(
+            spend(&mut game, face_down, Counter::Power).err(),
+            Some(ActionError::CardNotInstalled)
+        );
+
+        let in_hand = in_hand(&mut game, Faction::Sanctum, "asset");
+        assert_eq!(
+            spend(&mut game, in_hand, Counter::Power).err(),
+            Some(ActionError::CardNotInstalled)
+        );
+
+        let asset = installed(&mut game, "asset", false);
+        game.add_counters(asset, Counter::Virus, 1);
+        assert_eq!(
+            spend(&mut game, asset, Counter::Virus).err(),
+            Some(ActionError::NoCounterAbility)
+        );
+        assert_eq!(game.counter(asset, Counter::Virus), 1);
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA);
+    }
+}
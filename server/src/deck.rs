@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use common::card::{CardData, Faction};
+use common::deck::{DeckChoice, DeckList, load_deck_from_json};
+use common::{CardId, InstanceId};
+use rand::Rng;
+
+use crate::instances::{Instances, InstantiateLocation};
+
+pub const STARTING_HAND_SIZE: usize = 5;
+pub const MIN_DECK_SIZE: u32 = 12;
+pub const MAX_COPIES_PER_CARD: u32 = 4;
+pub const DECKS_DIRECTORY: &str = "decks";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeckError {
+    UnknownDeck(String),
+    UnknownCard(CardId),
+    WrongFaction(CardId),
+    TooManyCopies(CardId, u32),
+    TooSmall(u32),
+}
+impl fmt::Display for DeckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeckError::UnknownDeck(name) => write!(f, "no deck named {}", name),
+            DeckError::UnknownCard(id) => write!(f, "unknown card {}", id),
+            DeckError::WrongFaction(id) => write!(f, "{} belongs to the other faction", id),
+            DeckError::TooManyCopies(id, count) => write!(
+                f,
+                "{} copies of {}, the limit is {}",
+                count, id, MAX_COPIES_PER_CARD
+            ),
+            DeckError::TooSmall(size) => write!(
+                f,
+                "deck has {} cards, the minimum is {}",
+                size, MIN_DECK_SIZE
+            ),
+        }
+    }
+}
+
+/// Loads a named deck from the decks directory, or takes the submitted list as is.
+pub fn resolve_deck(choice: &DeckChoice) -> Result<DeckList, DeckError> {
+    match choice {
+        DeckChoice::List(deck) => Ok(deck.clone()),
+        DeckChoice::Named(name) => {
+            // Names can't be used to reach outside of the decks directory.
+            if name.is_empty() || name.contains(['/', '\\', '.']) {
+                return Err(DeckError::UnknownDeck(name.clone()));
+            }
+            load_deck_from_json(&format!("{}/{}.json", DECKS_DIRECTORY, name))
+                .map_err(|_| DeckError::UnknownDeck(name.clone()))
+        }
+    }
+}
+
+pub fn validate_deck(
+    faction: Faction,
+    deck: &DeckList,
+    cards: &HashMap<CardId, CardData>,
+) -> Result<(), DeckError> {
+    for (id, &count) in deck.iter() {
+        let data = cards
+            .get(id)
+            .ok_or_else(|| DeckError::UnknownCard(id.clone()))?;
+        if data.faction != faction {
+            return Err(DeckError::WrongFaction(id.clone()));
+        }
+        if count > MAX_COPIES_PER_CARD {
+            return Err(DeckError::TooManyCopies(id.clone(), count));
+        }
+    }
+    let size = deck.values().sum();
+    if size < MIN_DECK_SIZE {
+        return Err(DeckError::TooSmall(size));
+    }
+    Ok(())
+}
+
+pub fn instantiate_deck(
+    deck: &DeckList,
+    instances: &mut Instances,
+    rng: &mut impl Rng,
+) -> Vec<InstanceId> {
+    let mut instantiated: Vec<InstanceId> = vec![];
+    for (id, &count) in deck.iter() {
+        for _ in 0..count {
+            instantiated.push(instances.create_instance(id, InstantiateLocation::Deck, rng));
+        }
+    }
+    instantiated
+}
+
+/// Fisher-Yates shuffle, the top of the deck is the end of the vector.
+pub fn shuffle(deck: &mut [InstanceId], rng: &mut impl Rng) {
+    for i in (1..deck.len()).rev() {
+        let j = rng.random_range(0..=i);
+        deck.swap(i, j);
+    }
+}
+
+pub fn create_hand(
+    deck: &mut Vec<InstanceId>,
+    instances: &mut Instances,
+    rng: &mut impl Rng,
+) -> Vec<InstanceId> {
+    let mut hand = vec![];
+    for _ in 0..STARTING_HAND_SIZE {
+        let Some(ins) = deck.pop() else {
+            break;
+        };
+        hand.push(instances.move_to(ins, InstantiateLocation::Hand, rng));
+    }
+
+    hand
+}
+
+#[cfg(test)]
+mod tests {
+    use common::card::{CardType, Mana};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::game_state::GameState;
+    use crate::testing::card;
+
+    fn cards() -> HashMap<CardId, CardData> {
+        ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|id| {
+                let faction = match id {
+                    "d" => Faction::Thief,
+                    _ => Faction::Sanctum,
+                };
+                (
+                    id.to_string(),
+                    card(id, faction, CardType::Event { cost: Mana(0) }),
+                )
+            })
+            .collect()
+    }
+
+    fn deck() -> DeckList {
+        DeckList::from([
+            ("a".to_string(), 4),
+            ("b".to_string(), 4),
+            ("c".to_string(), 4),
+        ])
+    }
+
+    /// The Sanctum's deck and hand, top card last, as card ids.
+    fn dealt(game: &GameState) -> Vec<CardId> {
+        game.sanctum
+            .hand
+            .iter()
+            .chain(game.sanctum.deck.iter())
+            .map(|&instance_id| game.instances.get_instantiated_card(instance_id).id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn a_seed_deals_the_same_game_every_time() {
+        let thief_deck = DeckList::from([("d".to_string(), 12)]);
+        let game = |seed| GameState::new(cards(), &deck(), &thief_deck, seed);
+        let (first, again, other) = (game(7), game(7), game(8));
+        assert_eq!(dealt(&first), dealt(&again));
+        assert_eq!(first.sanctum.deck, again.sanctum.deck);
+        assert_ne!(dealt(&first), dealt(&other));
+    }
+
+    #[test]
+    fn shuffling_keeps_every_card() {
+        let mut shuffled: Vec<InstanceId> = (1..=12).collect();
+        shuffle(&mut shuffled, &mut ChaCha8Rng::seed_from_u64(1));
+        assert_ne!(shuffled, (1..=12).collect::<Vec<_>>());
+        shuffled.sort();
+        assert_eq!(shuffled, (1..=12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn decks_need_enough_cards_of_their_own_faction() {
+        let cards = cards();
+        assert_eq!(validate_deck(Faction::Sanctum, &deck(), &cards), Ok(()));
+
+        let mut small = deck();
+        small.insert("c".to_string(), 3);
+        assert_eq!(
+            validate_deck(Faction::Sanctum, &small, &cards),
+            Err(DeckError::TooSmall(MIN_DECK_SIZE - 1))
+        );
+
+        let mut too_many = deck();
+        too_many.insert("a".to_string(), MAX_COPIES_PER_CARD + 1);
+        assert_eq!(
+            validate_deck(Faction::Sanctum, &too_many, &cards),
+            Err(DeckError::TooManyCopies(
+                "a".to_string(),
+                MAX_COPIES_PER_CARD + 1
+            ))
+        );
+
+        let mut unknown = deck();
+        unknown.insert("z".to_string(), 1);
+        assert_eq!(
+            validate_deck(Faction::Sanctum, &unknown, &cards),
+            Err(DeckError::UnknownCard("z".to_string()))
+        );
+        assert_eq!(
+            validate_deck(Faction::Thief, &deck(), &cards),
+            Err(DeckError::WrongFaction("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn deck_names_stay_inside_the_decks_directory() {
+        for name in ["", "../cards", "sub/deck", "deck.json"] {
+            assert_eq!(
+                resolve_deck(&DeckChoice::Named(name.to_string())),
+                Err(DeckError::UnknownDeck(name.to_string()))
+            );
+        }
+        assert_eq!(resolve_deck(&DeckChoice::List(deck())), Ok(deck()));
+    }
+}
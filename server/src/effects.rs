@@ -0,0 +1,56 @@
+use common::InstanceId;
+use common::card::{Effect, Faction};
+
+use crate::game_state::{GameState, Update};
+
+impl GameState {
+    /// Resolves `effects` in order on behalf of `controller`. `source` is the card
+    /// they come from. Stops early once the game is over.
+    pub(crate) fn resolve_effects(
+        &mut self,
+        controller: Faction,
+        source: InstanceId,
+        effects: &[Effect],
+    ) -> Vec<Update> {
+        let mut updates = vec![];
+        for effect in effects {
+            updates.extend(self.resolve_effect(controller, source, *effect));
+            if self.is_over() {
+                break;
+            }
+        }
+        updates
+    }
+
+    fn resolve_effect(
+        &mut self,
+        controller: Faction,
+        source: InstanceId,
+        effect: Effect,
+    ) -> Vec<Update> {
+        match effect {
+            Effect::GainMana(amount) => {
+                self.stats_mut(controller).mana_pool += amount;
+                vec![self.stats_update(controller)]
+            }
+            Effect::Draw(amount) => {
+                let mut updates = vec![];
+                for _ in 0..amount {
+                    let Ok(update) = self.draw_card(controller) else {
+                        break;
+                    };
+                    updates.push(update);
+                }
+                updates
+            }
+            Effect::DealDamage(amount) => self.deal_backlash(amount),
+            Effect::EndInfiltration => match self.infiltration {
+                Some(_) => self.end_infiltration(false),
+                None => vec![],
+            },
+            Effect::PlaceCounter(counter, amount) => {
+                vec![self.add_counters(source, counter, amount)]
+            }
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+
+use common::card::{CardType, Effect, Faction};
+use common::{InfiltrationStep, InstanceId, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::game_state::{ActionError, GameState, Update};
+
+/// The Thief's encounter with a rezzed ward during an Infiltration.
+#[derive(Serialize, Deserialize)]
+pub struct Encounter {
+    pub ward: InstanceId,
+    pub broken: Vec<bool>,
+    /// Strength bought for each counterspell, lost when the encounter ends.
+    pub boosts: HashMap<InstanceId, u32>,
+}
+
+impl GameState {
+    fn ward_subroutines(&self, ward: InstanceId) -> Vec<Effect> {
+        match &self.card_data(ward).data {
+            CardType::Ward { subroutines, .. } => subroutines.clone(),
+            _ => vec![],
+        }
+    }
+
+    pub(crate) fn begin_encounter(&mut self, position: usize) -> Vec<Update> {
+        let target = self.infiltration.as_ref().unwrap().target;
+        let ward = self.wards(target).unwrap()[position];
+        let subroutines = self.ward_subroutines(ward).len();
+        self.infiltration.as_mut().unwrap().encounter = Some(Encounter {
+            ward,
+            broken: vec![false; subroutines],
+            boosts: HashMap::new(),
+        });
+        self.set_infiltration_step(InfiltrationStep::EncounterWard(position))
+    }
+
+    /// Current strength of an installed counterspell, including paid boosts.
+    pub fn counterspell_strength(&self, counterspell: InstanceId) -> u32 {
+        let base = match &self.card_data(counterspell).data {
+            CardType::CounterSpell { strength, .. } => *strength,
+            _ => 0,
+        };
+        let boost = self
+            .infiltration
+            .as_ref()
+            .and_then(|infiltration| infiltration.encounter.as_ref())
+            .and_then(|encounter| encounter.boosts.get(&counterspell))
+            .copied()
+            .unwrap_or(0);
+        base + boost
+    }
+
+    fn encounter(&self) -> &Encounter {
+        self.infiltration
+            .as_ref()
+            .and_then(|infiltration| infiltration.encounter.as_ref())
+            .unwrap()
+    }
+    fn encounter_mut(&mut self) -> &mut Encounter {
+        self.infiltration
+            .as_mut()
+            .and_then(|infiltration| infiltration.encounter.as_mut())
+            .unwrap()
+    }
+
+    pub(crate) fn pump_strength(
+        &mut self,
+        counterspell: InstanceId,
+    ) -> Result<Vec<Update>, ActionError> {
+        if !self.thief.spell_slots.contains(&counterspell) {
+            return Err(ActionError::CardNotInstalled);
+        }
+        let CardType::CounterSpell {
+            pump: Some(pump), ..
+        } = self.card_data(counterspell).data
+        else {
+            return Err(ActionError::CannotBreak);
+        };
+        self.pay_mana(Faction::Thief, pump.cost.0)?;
+        *self.encounter_mut().boosts.entry(counterspell).or_default() += pump.amount;
+
+        Ok(vec![
+            Update::Public(Response::StrengthChanged {
+                card: counterspell,
+                strength: self.counterspell_strength(counterspell),
+            }),
+            self.stats_update(Faction::Thief),
+        ])
+    }
+
+    pub(crate) fn break_subroutines(
+        &mut self,
+        counterspell: InstanceId,
+        subroutines: &[usize],
+    ) -> Result<Vec<Update>, ActionError> {
+        if !self.thief.spell_slots.contains(&counterspell) {
+            return Err(ActionError::CardNotInstalled);
+        }
+        let CardType::CounterSpell {
+            subtype,
+            break_subroutines,
+            ..
+        } = self.card_data(counterspell).data
+        else {
+            return Err(ActionError::CannotBreak);
+        };
+        let ward = self.encounter().ward;
+        let CardType::Ward {
+            subtype: ward_subtype,
+            strength: ward_strength,
+            ..
+        } = self.card_data(ward).data
+        else {
+            return Err(ActionError::CannotBreak);
+        };
+        if subtype.breaks() != ward_subtype || break_subroutines.amount == 0 {
+            return Err(ActionError::CannotBreak);
+        }
+        if self.counterspell_strength(counterspell) < ward_strength {
+            return Err(ActionError::InsufficientStrength);
+        }
+        let broken = &self.encounter().broken;
+        for (i, &subroutine) in subroutines.iter().enumerate() {
+            if subroutine >= broken.len()
+                || broken[subroutine]
+                || subroutines[..i].contains(&subroutine)
+            {
+                return Err(ActionError::InvalidSubroutine);
+            }
+        }
+
+        let cost = (subroutines.len() as u32).div_ceil(break_subroutines.amount)
+            * break_subroutines.cost.0;
+        self.pay_mana(Faction::Thief, cost)?;
+
+        let mut updates = vec![];
+        for &subroutine in subroutines {
+            self.encounter_mut().broken[subroutine] = true;
+            updates.push(Update::Public(Response::SubroutineBroken {
+                ward,
+                subroutine,
+            }));
+        }
+        updates.push(self.stats_update(Faction::Thief));
+        Ok(updates)
+    }
+
+    /// Fires every subroutine the Thief didn't break, then moves past the ward
+    /// unless one of them ended the Infiltration.
+    pub(crate) fn resolve_encounter(&mut self, position: usize) -> Vec<Update> {
+        let encounter = self
+            .infiltration
+            .as_mut()
+            .and_then(|infiltration| infiltration.encounter.take())
+            .unwrap();
+
+        let mut updates = vec![];
+        for (subroutine, effect) in self.ward_subroutines(encounter.ward).iter().enumerate() {
+            if encounter.broken[subroutine] {
+                continue;
+            }
+            updates.push(Update::Public(Response::SubroutineFired {
+                ward: encounter.ward,
+                subroutine,
+            }));
+            updates.extend(self.resolve_effects(Faction::Sanctum, encounter.ward, &[*effect]));
+            if self.infiltration.is_none() || self.is_over() {
+                return updates;
+            }
+        }
+        updates.extend(self.pass_ward(position));
+        updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::card::{CounterSpellSubType, Mana, PaidAbility, WardSubType};
+    use common::deck::DeckList;
+    use common::{ServerTarget, Update};
+
+    use super::*;
+    use crate::infiltration::Infiltration;
+    use crate::instances::InstantiateLocation;
+    use crate::testing::card;
+
+    fn fracter(
+        strength: u32,
+        pump: Option<PaidAbility>,
+        break_subroutines: PaidAbility,
+    ) -> CardType {
+        CardType::CounterSpell {
+            subtype: CounterSpellSubType::Fracter,
+            cost: Mana(0),
+            strength,
+            focus_cost: 1,
+            pump,
+            break_subroutines,
+        }
+    }
+
+    /// A game where the Thief is encountering a rezzed glyph on the hand lair
+    /// with `counterspell` installed and 10 mana to spend.
+    fn encounter(
+        ward_strength: u32,
+        subroutines: Vec<Effect>,
+        counterspell: CardType,
+    ) -> (GameState, InstanceId, InstanceId) {
+        let ward = card(
+            "ward",
+            Faction::Sanctum,
+            CardType::Ward {
+                subtype: WardSubType::Glyph,
+                cost: Mana(0),
+                strength: ward_strength,
+                subroutines,
+            },
+        );
+        let cards = HashMap::from([
+            ("ward".to_string(), ward),
+            (
+                "spell".to_string(),
+                card("spell", Faction::Thief, counterspell),
+            ),
+        ]);
+        let sanctum_deck = DeckList::from([("ward".to_string(), 10)]);
+        let thief_deck = DeckList::from([("spell".to_string(), 10)]);
+        let mut game = GameState::new(cards, &sanctum_deck, &thief_deck, 1);
+
+        let ward = game.instances.create_instance(
+            &"ward".to_string(),
+            InstantiateLocation::Board(false),
+            &mut game.rng,
+        );
+        game.sanctum.hand_lair.push(ward);
+        let spell = game.instances.create_instance(
+            &"spell".to_string(),
+            InstantiateLocation::Board(false),
+            &mut game.rng,
+        );
+        game.thief.spell_slots.push(spell);
+        game.thief.stats.mana_pool = 10;
+        game.infiltration = Some(Infiltration {
+            target: ServerTarget::HandLair,
+            step: InfiltrationStep::ApproachWard(0),
+            to_access: vec![],
+            encounter: None,
+        });
+        game.begin_encounter(0);
+        (game, ward, spell)
+    }
+
+    fn ability(cost: u32, amount: u32) -> PaidAbility {
+        PaidAbility {
+            cost: Mana(cost),
+            amount,
+        }
+    }
+
+    #[test]
+    fn breaking_needs_enough_strength() {
+        let (mut game, _, spell) = encounter(
+            2,
+            vec![Effect::EndInfiltration],
+            fracter(1, None, ability(1, 1)),
+        );
+        assert_eq!(
+            game.break_subroutines(spell, &[0]).err(),
+            Some(ActionError::InsufficientStrength)
+        );
+        assert_eq!(game.thief.stats.mana_pool, 10);
+    }
+
+    #[test]
+    fn pumping_costs_per_boost_and_lasts_the_encounter() {
+        let (mut game, _, spell) = encounter(
+            3,
+            vec![Effect::EndInfiltration],
+            fracter(1, Some(ability(2, 1)), ability(1, 1)),
+        );
+        game.pump_strength(spell).unwrap();
+        assert_eq!(game.counterspell_strength(spell), 2);
+        game.pump_strength(spell).unwrap();
+        assert_eq!(game.counterspell_strength(spell), 3);
+        assert_eq!(game.thief.stats.mana_pool, 6);
+        game.break_subroutines(spell, &[0]).unwrap();
+
+        game.resolve_encounter(0);
+        assert_eq!(game.counterspell_strength(spell), 1);
+    }
+
+    #[test]
+    fn pumping_without_a_pump_ability_is_refused() {
+        let (mut game, _, spell) = encounter(
+            3,
+            vec![Effect::EndInfiltration],
+            fracter(1, None, ability(1, 1)),
+        );
+        assert_eq!(
+            game.pump_strength(spell).err(),
+            Some(ActionError::CannotBreak)
+        );
+    }
+
+    #[test]
+    fn subroutines_must_exist_and_be_unbroken() {
+        let (mut game, _, spell) = encounter(
+            0,
+            vec![Effect::GainMana(1), Effect::EndInfiltration],
+            fracter(1, None, ability(1, 1)),
+        );
+        for subroutines in [&[2][..], &[0, 0][..]] {
+            assert_eq!(
+                game.break_subroutines(spell, subroutines).err(),
+                Some(ActionError::InvalidSubroutine)
+            );
+        }
+        game.break_subroutines(spell, &[1]).unwrap();
+        assert_eq!(
+            game.break_subroutines(spell, &[1]).err(),
+            Some(ActionError::InvalidSubroutine)
+        );
+        assert_eq!(game.thief.stats.mana_pool, 9);
+    }
+
+    #[test]
+    fn breaking_costs_round_up_per_batch() {
+        let (mut game, ward, spell) = encounter(
+            0,
+            vec![Effect::GainMana(1); 3],
+            fracter(1, None, ability(2, 2)),
+        );
+        let updates = game.break_subroutines(spell, &[0, 1, 2]).unwrap();
+        // Three subroutines at two per use is two uses.
+        assert_eq!(game.thief.stats.mana_pool, 6);
+        let broken = updates
+            .iter()
+            .filter(|update| {
+                matches!(update, Update::Public(Response::SubroutineBroken { ward: broken, .. })
+                    if *broken == ward)
+            })
+            .count();
+        assert_eq!(broken, 3);
+    }
+
+    #[test]
+    fn unbroken_subroutines_fire() {
+        let (mut game, _, spell) = encounter(
+            0,
+            vec![Effect::GainMana(2), Effect::EndInfiltration],
+            fracter(1, None, ability(1, 1)),
+        );
+        let mana = game.sanctum.stats.mana_pool;
+        game.break_subroutines(spell, &[1]).unwrap();
+        let updates = game.resolve_encounter(0);
+        let fired: Vec<usize> = updates
+            .iter()
+            .filter_map(|update| match update {
+                Update::Public(Response::SubroutineFired { subroutine, .. }) => Some(*subroutine),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(fired, vec![0]);
+        assert_eq!(game.sanctum.stats.mana_pool, mana + 2);
+        // The broken subroutine would have ended it.
+        assert!(game.infiltration.is_some());
+
+        let (mut game, _, _) = encounter(
+            0,
+            vec![Effect::EndInfiltration, Effect::GainMana(2)],
+            fracter(1, None, ability(1, 1)),
+        );
+        let mana = game.sanctum.stats.mana_pool;
+        game.resolve_encounter(0);
+        // Nothing after the subroutine that ended the infiltration fires.
+        assert!(game.infiltration.is_none());
+        assert_eq!(game.sanctum.stats.mana_pool, mana);
+    }
+}
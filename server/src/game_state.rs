@@ -0,0 +1,865 @@
+use std::collections::HashMap;
+
+use common::card::{CardData, CardState, CardType, Faction};
+use common::deck::DeckList;
+use common::rules::{SANCTUM_STAMINA, STARTING_MANA, THIEF_STAMINA};
+pub use common::{ActionError, Update};
+use common::{
+    ActionReq, BasicStats, CardId, CommonState, GameOverReason, InfiltrationStep,
+    InitStateResponse, InstanceId, PlayerStateResponse, RemoteRes, Response, SanctumState,
+    SanctumStateInternal, ThiefState, ThiefStateInternal,
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::deck::{create_hand, instantiate_deck, shuffle};
+use crate::infiltration::Infiltration;
+use crate::instances::{Instances, InstantiateLocation};
+
+pub fn other_faction(faction: Faction) -> Faction {
+    match faction {
+        Faction::Sanctum => Faction::Thief,
+        Faction::Thief => Faction::Sanctum,
+    }
+}
+
+/// The authoritative state of a single match between a Sanctum and a Thief.
+#[derive(Serialize, Deserialize)]
+pub struct GameState {
+    pub cards: HashMap<CardId, CardData>,
+    pub instances: Instances,
+    pub sanctum: SanctumStateInternal,
+    pub thief: ThiefStateInternal,
+    pub turn: Faction,
+    pub turn_number: u32,
+    pub infiltration: Option<Infiltration>,
+    pub outcome: Option<(Faction, GameOverReason)>,
+    /// Every random decision in the game comes from here, so a seed replays a game.
+    pub seed: u64,
+    pub rng: ChaCha8Rng,
+}
+impl GameState {
+    /// Both deck lists are expected to have gone through `validate_deck` already.
+    pub fn new(
+        cards: HashMap<CardId, CardData>,
+        sanctum_deck: &DeckList,
+        thief_deck: &DeckList,
+        seed: u64,
+    ) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut instances = Instances::new();
+        let mut sanc_deck = instantiate_deck(sanctum_deck, &mut instances, &mut rng);
+        shuffle(&mut sanc_deck, &mut rng);
+        let sanc_hand = create_hand(&mut sanc_deck, &mut instances, &mut rng);
+        let mut thief_deck = instantiate_deck(thief_deck, &mut instances, &mut rng);
+        shuffle(&mut thief_deck, &mut rng);
+        let thief_hand = create_hand(&mut thief_deck, &mut instances, &mut rng);
+        let stats = BasicStats {
+            mana_pool: STARTING_MANA,
+            stamina: 0,
+            score: 0,
+        };
+
+        let mut game = Self {
+            cards,
+            instances,
+            sanctum: SanctumStateInternal {
+                stats,
+                deck: sanc_deck,
+                hand: sanc_hand,
+                discard: vec![],
+                score_area: vec![],
+                hand_lair: vec![],
+                deck_lair: vec![],
+                discard_lair: vec![],
+                remotes: vec![],
+            },
+            thief: ThiefStateInternal {
+                stats,
+                deck: thief_deck,
+                hand: thief_hand,
+                discard: vec![],
+                score_area: vec![],
+                spell_slots: vec![],
+                gear_slots: vec![],
+                ally_slots: vec![],
+            },
+            turn: Faction::Sanctum,
+            turn_number: 0,
+            infiltration: None,
+            outcome: None,
+            seed,
+            rng,
+        };
+        // Nobody is connected yet, the opening draw is part of the initial state.
+        game.start_turn(Faction::Sanctum);
+        game
+    }
+
+    pub fn stats(&self, faction: Faction) -> &BasicStats {
+        match faction {
+            Faction::Sanctum => &self.sanctum.stats,
+            Faction::Thief => &self.thief.stats,
+        }
+    }
+    pub(crate) fn stats_mut(&mut self, faction: Faction) -> &mut BasicStats {
+        match faction {
+            Faction::Sanctum => &mut self.sanctum.stats,
+            Faction::Thief => &mut self.thief.stats,
+        }
+    }
+    pub fn card_data(&self, instance_id: InstanceId) -> &CardData {
+        &self.cards[&self.instances.get_instantiated_card(instance_id).id]
+    }
+    pub fn is_rezzed(&self, instance_id: InstanceId) -> bool {
+        matches!(
+            self.instances.get_instantiated_card(instance_id).location,
+            InstantiateLocation::Board(false)
+        )
+    }
+    /// The faction whose decision the game is currently waiting on.
+    pub fn priority(&self) -> Faction {
+        match &self.infiltration {
+            Some(infiltration) => match infiltration.step {
+                InfiltrationStep::RezWindow(_) => Faction::Sanctum,
+                _ => Faction::Thief,
+            },
+            None => self.turn,
+        }
+    }
+    pub(crate) fn deck_and_hand_mut(
+        &mut self,
+        faction: Faction,
+    ) -> (&mut Vec<InstanceId>, &mut Vec<InstanceId>) {
+        match faction {
+            Faction::Sanctum => (&mut self.sanctum.deck, &mut self.sanctum.hand),
+            Faction::Thief => (&mut self.thief.deck, &mut self.thief.hand),
+        }
+    }
+
+    /// Applies an action sent by `faction` and returns the updates to broadcast.
+    pub fn handle_action(
+        &mut self,
+        faction: Faction,
+        action: &ActionReq,
+    ) -> Result<Vec<Update>, ActionError> {
+        if self.outcome.is_some() {
+            return Err(ActionError::GameOver);
+        }
+        if faction != self.priority() {
+            return Err(ActionError::NotYourTurn);
+        }
+        self.check_ownership(faction, action)?;
+
+        let mut updates = match action {
+            ActionReq::DrawCard
+            | ActionReq::GainMana
+            | ActionReq::Infiltrate(_)
+            | ActionReq::Attune(_)
+            | ActionReq::Install { .. }
+            | ActionReq::InstallRig(_)
+            | ActionReq::PlayCard(_) => self.handle_basic_action(faction, action)?,
+            ActionReq::ScoreArtifact(instance_id) => {
+                if self.infiltration.is_some() {
+                    return Err(ActionError::InfiltrationInProgress);
+                }
+                self.score_artifact(faction, *instance_id)?
+            }
+            ActionReq::Rez(instance_id) => self.handle_rez(*instance_id)?,
+            ActionReq::SpendCounter { card, counter } => {
+                self.spend_counter(faction, *card, *counter)?
+            }
+            ActionReq::Continue
+            | ActionReq::JackOut
+            | ActionReq::PumpStrength(_)
+            | ActionReq::BreakSubroutines { .. } => self.handle_infiltration_action(action)?,
+            ActionReq::Init(_)
+            | ActionReq::ListTables
+            | ActionReq::CreateTable(_)
+            | ActionReq::Resume(_)
+            | ActionReq::Spectate(_) => {
+                return Err(ActionError::UnsupportedAction);
+            }
+        };
+
+        // A turn can't end in the middle of an Infiltration.
+        if self.outcome.is_none()
+            && self.infiltration.is_none()
+            && self.stats(self.turn).stamina == 0
+        {
+            updates.extend(self.start_turn(other_faction(self.turn)));
+        }
+        Ok(updates)
+    }
+
+    /// Every card named by an action has to exist and belong to the player sending it.
+    fn check_ownership(&self, faction: Faction, action: &ActionReq) -> Result<(), ActionError> {
+        let instance_id = match action {
+            ActionReq::Rez(instance_id)
+            | ActionReq::PumpStrength(instance_id)
+            | ActionReq::Attune(instance_id)
+            | ActionReq::ScoreArtifact(instance_id)
+            | ActionReq::InstallRig(instance_id)
+            | ActionReq::PlayCard(instance_id)
+            | ActionReq::SpendCounter {
+                card: instance_id, ..
+            }
+            | ActionReq::Install {
+                card: instance_id, ..
+            }
+            | ActionReq::BreakSubroutines {
+                counterspell: instance_id,
+                ..
+            } => *instance_id,
+            _ => return Ok(()),
+        };
+        let card = self
+            .instances
+            .get(instance_id)
+            .ok_or(ActionError::UnknownCard)?;
+        if self.cards[&card.id].faction != faction {
+            return Err(ActionError::NotYourCard);
+        }
+        Ok(())
+    }
+
+    /// Actions that cost one stamina and can only be taken outside of an Infiltration.
+    fn handle_basic_action(
+        &mut self,
+        faction: Faction,
+        action: &ActionReq,
+    ) -> Result<Vec<Update>, ActionError> {
+        if self.infiltration.is_some() {
+            return Err(ActionError::InfiltrationInProgress);
+        }
+        if self.stats(faction).stamina == 0 {
+            return Err(ActionError::NotEnoughStamina);
+        }
+
+        let mut updates = vec![];
+        match action {
+            ActionReq::DrawCard => {
+                updates.push(self.draw_card(faction)?);
+            }
+            ActionReq::GainMana => {
+                self.stats_mut(faction).mana_pool += 1;
+            }
+            ActionReq::Infiltrate(target) => {
+                updates.extend(self.start_infiltration(faction, *target)?);
+            }
+            ActionReq::Attune(instance_id) => {
+                updates.extend(self.attune(faction, *instance_id)?);
+            }
+            ActionReq::Install { card, server } => {
+                updates.extend(self.install(faction, *card, *server)?);
+            }
+            ActionReq::InstallRig(instance_id) => {
+                updates.extend(self.install_rig(faction, *instance_id)?);
+            }
+            ActionReq::PlayCard(instance_id) => {
+                updates.extend(self.play_card(faction, *instance_id)?);
+            }
+            _ => return Err(ActionError::UnsupportedAction),
+        }
+        self.stats_mut(faction).stamina -= 1;
+        updates.push(self.stats_update(faction));
+        Ok(updates)
+    }
+
+    fn start_turn(&mut self, faction: Faction) -> Vec<Update> {
+        self.turn = faction;
+        self.turn_number += 1;
+        self.stats_mut(faction).stamina = match faction {
+            Faction::Sanctum => SANCTUM_STAMINA,
+            Faction::Thief => THIEF_STAMINA,
+        };
+
+        let mut updates = vec![
+            Update::Public(Response::TurnStarted {
+                turn: faction,
+                turn_number: self.turn_number,
+            }),
+            self.stats_update(faction),
+        ];
+        // The Sanctum's mandatory draw doesn't cost stamina.
+        if faction == Faction::Sanctum {
+            match self.draw_card(faction) {
+                Ok(update) => updates.push(update),
+                Err(_) => updates.push(self.end_game(Faction::Thief, GameOverReason::DeckOut)),
+            }
+        }
+        updates
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// Locks the match, every action after this one is rejected.
+    pub(crate) fn end_game(&mut self, winner: Faction, reason: GameOverReason) -> Update {
+        self.outcome = Some((winner, reason));
+        self.infiltration = None;
+        Update::Public(Response::GameOver { winner, reason })
+    }
+
+    /// Draws the top card of the deck, which is the end of the vector. The
+    /// opponent only learns that a card was drawn.
+    pub(crate) fn draw_card(&mut self, faction: Faction) -> Result<Update, ActionError> {
+        let (deck, _) = self.deck_and_hand_mut(faction);
+        let top = deck.pop().ok_or(ActionError::EmptyDeck)?;
+        let instance_id = self
+            .instances
+            .move_to(top, InstantiateLocation::Hand, &mut self.rng);
+        self.deck_and_hand_mut(faction).1.push(instance_id);
+
+        Ok(Update::Private {
+            owner: faction,
+            owner_view: Response::DrawCard {
+                faction,
+                card: Some(self.instances.create_card_state(instance_id, true)),
+            },
+            other_view: Response::DrawCard {
+                faction,
+                card: None,
+            },
+        })
+    }
+
+    pub(crate) fn pay_mana(&mut self, faction: Faction, amount: u32) -> Result<(), ActionError> {
+        let stats = self.stats_mut(faction);
+        if stats.mana_pool < amount {
+            return Err(ActionError::NotEnoughMana);
+        }
+        stats.mana_pool -= amount;
+        Ok(())
+    }
+
+    /// Magical Backlash makes the Thief discard that many cards at random, the
+    /// Thief flatlines if there aren't enough cards to discard.
+    pub(crate) fn deal_backlash(&mut self, amount: u32) -> Vec<Update> {
+        let mut updates = vec![];
+        for _ in 0..amount {
+            if self.thief.hand.is_empty() {
+                updates.push(self.end_game(Faction::Sanctum, GameOverReason::Flatline));
+                break;
+            }
+            let index = self.rng.random_range(0..self.thief.hand.len());
+            let from_hand = self.thief.hand.remove(index);
+            let instance_id =
+                self.instances
+                    .move_to(from_hand, InstantiateLocation::Trash, &mut self.rng);
+            self.thief.discard.push(instance_id);
+            let card = self.instances.create_card_state(instance_id, true);
+            updates.push(Update::Private {
+                owner: Faction::Thief,
+                owner_view: Response::Discarded {
+                    faction: Faction::Thief,
+                    card: card.clone(),
+                    from_hand: Some(from_hand),
+                },
+                other_view: Response::Discarded {
+                    faction: Faction::Thief,
+                    card,
+                    from_hand: None,
+                },
+            });
+        }
+        updates
+    }
+
+    /// Wards can only be rezzed as they're approached, which also starts the
+    /// encounter. Assets can be rezzed whenever the Sanctum has priority.
+    fn handle_rez(&mut self, instance_id: InstanceId) -> Result<Vec<Update>, ActionError> {
+        let in_rez_window = matches!(
+            self.infiltration
+                .as_ref()
+                .map(|infiltration| infiltration.step),
+            Some(InfiltrationStep::RezWindow(_))
+        );
+        match self.card_data(instance_id).data {
+            CardType::Ward { .. } if in_rez_window => {
+                self.handle_infiltration_action(&ActionReq::Rez(instance_id))
+            }
+            CardType::Asset { .. } => self.rez(instance_id),
+            _ => Err(ActionError::CannotRez),
+        }
+    }
+
+    /// Turns a face-down Ward or Asset face-up, paying its rez cost.
+    pub(crate) fn rez(&mut self, instance_id: InstanceId) -> Result<Vec<Update>, ActionError> {
+        if !matches!(
+            self.instances.get_instantiated_card(instance_id).location,
+            InstantiateLocation::Board(true)
+        ) {
+            return Err(ActionError::CannotRez);
+        }
+        let cost = match self.card_data(instance_id).data {
+            CardType::Ward { cost, .. } | CardType::Asset { cost, .. } => cost.0,
+            _ => return Err(ActionError::CannotRez),
+        };
+        self.pay_mana(Faction::Sanctum, cost)?;
+        self.instances
+            .get_mut_instantiated_card(instance_id)
+            .location = InstantiateLocation::Board(false);
+
+        Ok(vec![
+            Update::Public(Response::Reveal {
+                card: self.instances.create_card_state(instance_id, true),
+            }),
+            self.stats_update(Faction::Sanctum),
+        ])
+    }
+
+    pub(crate) fn stats_update(&self, faction: Faction) -> Update {
+        Update::Public(Response::StatsChanged {
+            faction,
+            stats: *self.stats(faction),
+        })
+    }
+
+    /// Face-down cards only show their faces to their owner.
+    fn installed_card_states(&self, instances: &[InstanceId], owner: bool) -> Vec<CardState> {
+        instances
+            .iter()
+            .map(|&instance_id| {
+                self.instances
+                    .create_card_state(instance_id, owner || self.is_rezzed(instance_id))
+            })
+            .collect()
+    }
+
+    /// Hidden zones are only sent as counts, and only the owner (or an omniscient
+    /// spectator) sees the hand.
+    fn player_state_response(&self, faction: Faction, owner: bool) -> PlayerStateResponse {
+        match faction {
+            Faction::Sanctum => PlayerStateResponse::Sanctum {
+                common: CommonState {
+                    stats: self.sanctum.stats,
+                    deck_size: self.sanctum.deck.len() as u32,
+                    hand_size: self.sanctum.hand.len() as u32,
+                    hand: match owner {
+                        true => self.instances.create_card_states(&self.sanctum.hand, true),
+                        false => vec![],
+                    },
+                    discard: self
+                        .instances
+                        .create_card_states(&self.sanctum.discard, true),
+                    score_area: self
+                        .instances
+                        .create_card_states(&self.sanctum.score_area, true),
+                },
+                specific: SanctumState {
+                    hand_lair: Some(self.installed_card_states(&self.sanctum.hand_lair, owner)),
+                    deck_lair: Some(self.installed_card_states(&self.sanctum.deck_lair, owner)),
+                    discard_lair: Some(
+                        self.installed_card_states(&self.sanctum.discard_lair, owner),
+                    ),
+                    remotes: Some(
+                        self.sanctum
+                            .remotes
+                            .iter()
+                            .map(|remote| RemoteRes {
+                                wards: self.installed_card_states(&remote.wards, owner),
+                                contents: remote.contents.map(|contents| {
+                                    self.installed_card_states(&[contents], owner).remove(0)
+                                }),
+                            })
+                            .collect(),
+                    ),
+                    rezzed: self
+                        .sanctum
+                        .installed()
+                        .filter(|&instance_id| self.is_rezzed(instance_id))
+                        .collect(),
+                },
+            },
+            Faction::Thief => PlayerStateResponse::Thief {
+                common: CommonState {
+                    stats: self.thief.stats,
+                    deck_size: self.thief.deck.len() as u32,
+                    hand_size: self.thief.hand.len() as u32,
+                    hand: match owner {
+                        true => self.instances.create_card_states(&self.thief.hand, true),
+                        false => vec![],
+                    },
+                    discard: self.instances.create_card_states(&self.thief.discard, true),
+                    score_area: self
+                        .instances
+                        .create_card_states(&self.thief.score_area, true),
+                },
+                specific: ThiefState {
+                    spell_slots: Some(
+                        self.instances
+                            .create_card_states(&self.thief.spell_slots, true),
+                    ),
+                    gear_slots: Some(
+                        self.instances
+                            .create_card_states(&self.thief.gear_slots, true),
+                    ),
+                    ally_slots: Some(
+                        self.instances
+                            .create_card_states(&self.thief.ally_slots, true),
+                    ),
+                },
+            },
+        }
+    }
+
+    pub fn init_state_response(&self, faction: Faction) -> InitStateResponse {
+        self.state_response(
+            self.player_state_response(faction, true),
+            self.player_state_response(other_faction(faction), false),
+        )
+    }
+
+    /// Spectators watch from the Sanctum's side of the table.
+    pub fn spectator_state_response(&self, omniscient: bool) -> InitStateResponse {
+        self.state_response(
+            self.player_state_response(Faction::Sanctum, omniscient),
+            self.player_state_response(Faction::Thief, omniscient),
+        )
+    }
+
+    fn state_response(
+        &self,
+        my_state: PlayerStateResponse,
+        other_state: PlayerStateResponse,
+    ) -> InitStateResponse {
+        InitStateResponse {
+            my_state: Some(my_state),
+            other_state: Some(other_state),
+            card_set: self.cards.clone(),
+            turn: self.turn,
+            turn_number: self.turn_number,
+            infiltration: self
+                .infiltration
+                .as_ref()
+                .map(|infiltration| (infiltration.target, infiltration.step)),
+            game_over: self.outcome,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::Remote;
+    use common::card::{AssetSubType, Effect, Mana, WardSubType};
+
+    use super::*;
+    use crate::deck::STARTING_HAND_SIZE;
+    use crate::testing::{card, game, instance, turn};
+
+    fn basic_game() -> GameState {
+        game(vec![
+            card(
+                "operation",
+                Faction::Sanctum,
+                CardType::Operation {
+                    subtype: None,
+                    cost: Mana(0),
+                },
+            ),
+            card(
+                "ward",
+                Faction::Sanctum,
+                CardType::Ward {
+                    subtype: WardSubType::Glyph,
+                    cost: Mana(1),
+                    strength: 1,
+                    subroutines: vec![Effect::EndInfiltration],
+                },
+            ),
+            card(
+                "asset",
+                Faction::Sanctum,
+                CardType::Asset {
+                    subtype: AssetSubType::Ritual,
+                    cost: Mana(2),
+                },
+            ),
+            card("event", Faction::Thief, CardType::Event { cost: Mana(0) }),
+        ])
+    }
+
+    #[test]
+    fn the_sanctum_opens_with_a_free_draw() {
+        let game = basic_game();
+        assert_eq!(game.turn, Faction::Sanctum);
+        assert_eq!(game.turn_number, 1);
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA);
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA);
+        assert_eq!(game.sanctum.hand.len(), STARTING_HAND_SIZE + 1);
+        assert_eq!(game.thief.hand.len(), STARTING_HAND_SIZE);
+        assert_eq!(game.thief.stats.stamina, 0);
+    }
+
+    #[test]
+    fn the_turn_passes_once_stamina_runs_out() {
+        let mut game = basic_game();
+        for _ in 0..SANCTUM_STAMINA {
+            assert_eq!(game.turn, Faction::Sanctum);
+            game.handle_action(Faction::Sanctum, &ActionReq::GainMana)
+                .unwrap();
+        }
+        assert_eq!(
+            game.sanctum.stats.mana_pool,
+            STARTING_MANA + SANCTUM_STAMINA
+        );
+        assert_eq!(game.turn, Faction::Thief);
+        assert_eq!(game.turn_number, 2);
+        assert_eq!(game.thief.stats.stamina, THIEF_STAMINA);
+
+        let hand = game.sanctum.hand.len();
+        for _ in 0..THIEF_STAMINA {
+            game.handle_action(Faction::Thief, &ActionReq::DrawCard)
+                .unwrap();
+        }
+        assert_eq!(
+            game.thief.hand.len(),
+            STARTING_HAND_SIZE + THIEF_STAMINA as usize
+        );
+        assert_eq!(game.turn, Faction::Sanctum);
+        assert_eq!(game.turn_number, 3);
+        // Drawn at the start of the turn, without spending stamina.
+        assert_eq!(game.sanctum.hand.len(), hand + 1);
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA);
+    }
+
+    #[test]
+    fn only_the_player_whose_turn_it_is_acts() {
+        let mut game = basic_game();
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::GainMana)
+                .err(),
+            Some(ActionError::NotYourTurn)
+        );
+        assert_eq!(game.thief.stats.mana_pool, STARTING_MANA);
+    }
+
+    #[test]
+    fn actions_need_stamina() {
+        let mut game = basic_game();
+        game.sanctum.stats.stamina = 0;
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::GainMana)
+                .err(),
+            Some(ActionError::NotEnoughStamina)
+        );
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA);
+    }
+
+    #[test]
+    fn a_refused_draw_costs_no_stamina() {
+        let mut game = basic_game();
+        game.sanctum.deck.clear();
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::DrawCard)
+                .err(),
+            Some(ActionError::EmptyDeck)
+        );
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA);
+    }
+
+    #[test]
+    fn backlash_beyond_the_thiefs_hand_flatlines_them() {
+        let mut game = basic_game();
+        let updates = game.deal_backlash(STARTING_HAND_SIZE as u32 + 1);
+        assert_eq!(
+            game.outcome,
+            Some((Faction::Sanctum, GameOverReason::Flatline))
+        );
+        assert!(game.thief.hand.is_empty());
+        assert_eq!(game.thief.discard.len(), STARTING_HAND_SIZE);
+        assert!(matches!(
+            updates.last(),
+            Some(Update::Public(Response::GameOver {
+                winner: Faction::Sanctum,
+                reason: GameOverReason::Flatline,
+            }))
+        ));
+    }
+
+    #[test]
+    fn the_sanctum_decks_out_when_it_cant_draw_for_its_turn() {
+        let mut game = basic_game();
+        game.sanctum.deck.clear();
+        game.turn = Faction::Thief;
+        game.thief.stats.stamina = 1;
+        game.handle_action(Faction::Thief, &ActionReq::GainMana)
+            .unwrap();
+        assert_eq!(
+            game.outcome,
+            Some((Faction::Thief, GameOverReason::DeckOut))
+        );
+    }
+
+    #[test]
+    fn nothing_is_accepted_once_the_game_is_over() {
+        let mut game = basic_game();
+        game.end_game(Faction::Thief, GameOverReason::DeckOut);
+        assert!(game.is_over());
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::GainMana)
+                .err(),
+            Some(ActionError::GameOver)
+        );
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA);
+    }
+
+    #[test]
+    fn cards_named_by_an_action_must_be_the_players_own() {
+        let mut game = basic_game();
+        turn(&mut game, Faction::Thief);
+        let theirs = game.sanctum.hand[0];
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::PlayCard(theirs))
+                .err(),
+            Some(ActionError::NotYourCard)
+        );
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::PlayCard(0))
+                .err(),
+            Some(ActionError::UnknownCard)
+        );
+        assert_eq!(game.sanctum.hand[0], theirs);
+        assert_eq!(game.thief.stats.stamina, THIEF_STAMINA);
+
+        let mine = game.thief.hand[0];
+        game.handle_action(Faction::Thief, &ActionReq::PlayCard(mine))
+            .unwrap();
+        assert_eq!(game.thief.discard.len(), 1);
+    }
+
+    #[test]
+    fn lobby_requests_are_not_game_actions() {
+        let mut game = basic_game();
+        for action in [
+            ActionReq::ListTables,
+            ActionReq::CreateTable("table".to_string()),
+            ActionReq::Spectate("table".to_string()),
+        ] {
+            assert_eq!(
+                game.handle_action(Faction::Sanctum, &action).err(),
+                Some(ActionError::UnsupportedAction)
+            );
+        }
+    }
+
+    fn sanctum_state(state: Option<PlayerStateResponse>) -> (CommonState, SanctumState) {
+        match state {
+            Some(PlayerStateResponse::Sanctum { common, specific }) => (common, specific),
+            _ => panic!("not the Sanctum's state"),
+        }
+    }
+
+    #[test]
+    fn hidden_zones_only_reach_their_owner() {
+        let mut game = basic_game();
+        let hidden = instance(&mut game, "ward", InstantiateLocation::Board(true));
+        let rezzed = instance(&mut game, "ward", InstantiateLocation::Board(false));
+        game.sanctum.hand_lair.extend([hidden, rezzed]);
+
+        let thief_view = game.init_state_response(Faction::Thief);
+        let (common, specific) = sanctum_state(thief_view.other_state);
+        assert!(common.hand.is_empty());
+        assert_eq!(common.hand_size, game.sanctum.hand.len() as u32);
+        assert_eq!(common.deck_size, game.sanctum.deck.len() as u32);
+        let lair = specific.hand_lair.unwrap();
+        assert!(matches!(lair[0], CardState::Hidden(id, _) if id == hidden));
+        assert!(matches!(lair[1], CardState::Revealed(id, ..) if id == rezzed));
+        assert_eq!(specific.rezzed, vec![rezzed]);
+        let mine = thief_view.my_state.unwrap();
+        assert_eq!(mine.get_common().hand.len(), game.thief.hand.len());
+
+        let sanctum_view = game.init_state_response(Faction::Sanctum);
+        let (common, specific) = sanctum_state(sanctum_view.my_state);
+        assert_eq!(common.hand.len(), game.sanctum.hand.len());
+        let lair = specific.hand_lair.unwrap();
+        assert!(
+            lair.iter()
+                .all(|card| matches!(card, CardState::Revealed(..)))
+        );
+        // The owner sees every face, `rezzed` says which ones the Thief sees too.
+        assert_eq!(specific.rezzed, vec![rezzed]);
+        let other = sanctum_view.other_state.unwrap();
+        assert!(other.get_common().hand.is_empty());
+        assert_eq!(other.get_common().hand_size, game.thief.hand.len() as u32);
+    }
+
+    #[test]
+    fn only_omniscient_spectators_see_the_hands() {
+        let game = basic_game();
+        let public = game.spectator_state_response(false);
+        for state in [&public.my_state, &public.other_state] {
+            assert!(state.as_ref().unwrap().get_common().hand.is_empty());
+        }
+        let omniscient = game.spectator_state_response(true);
+        assert_eq!(
+            omniscient.my_state.unwrap().get_common().hand.len(),
+            game.sanctum.hand.len()
+        );
+        assert_eq!(
+            omniscient.other_state.unwrap().get_common().hand.len(),
+            game.thief.hand.len()
+        );
+    }
+
+    /// `card` installed face-down in a remote of its own.
+    fn installed(game: &mut GameState, card: &str) -> InstanceId {
+        let instance_id = instance(game, card, InstantiateLocation::Board(true));
+        game.sanctum.remotes.push(Remote {
+            wards: vec![],
+            contents: Some(instance_id),
+        });
+        instance_id
+    }
+
+    #[test]
+    fn assets_rez_whenever_the_sanctum_has_priority() {
+        let mut game = basic_game();
+        let asset = installed(&mut game, "asset");
+        let updates = game
+            .handle_action(Faction::Sanctum, &ActionReq::Rez(asset))
+            .unwrap();
+        assert!(game.is_rezzed(asset));
+        assert!(matches!(&updates[0],
+            Update::Public(Response::Reveal { card: CardState::Revealed(id, ..) }) if *id == asset));
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA - 2);
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA);
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::Rez(asset))
+                .err(),
+            Some(ActionError::CannotRez)
+        );
+    }
+
+    #[test]
+    fn rezzing_needs_mana() {
+        let mut game = basic_game();
+        let asset = installed(&mut game, "asset");
+        game.sanctum.stats.mana_pool = 1;
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::Rez(asset))
+                .err(),
+            Some(ActionError::NotEnoughMana)
+        );
+        assert!(!game.is_rezzed(asset));
+        assert_eq!(game.sanctum.stats.mana_pool, 1);
+    }
+
+    #[test]
+    fn wards_only_rez_as_they_are_approached() {
+        let mut game = basic_game();
+        let ward = instance(&mut game, "ward", InstantiateLocation::Board(true));
+        game.sanctum.hand_lair.push(ward);
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::Rez(ward))
+                .err(),
+            Some(ActionError::CannotRez)
+        );
+        assert!(!game.is_rezzed(ward));
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA);
+    }
+}
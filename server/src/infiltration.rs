@@ -0,0 +1,347 @@
+use common::card::Faction;
+use common::{ActionReq, InfiltrationStep, InstanceId, Response, ServerTarget};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::encounter::Encounter;
+use crate::game_state::{ActionError, GameState, Update};
+
+/// An Infiltration in progress against one of the Sanctum's servers.
+#[derive(Serialize, Deserialize)]
+pub struct Infiltration {
+    pub target: ServerTarget,
+    pub step: InfiltrationStep,
+    /// Cards left to access once the Thief breaches the server, last one first.
+    pub to_access: Vec<InstanceId>,
+    pub encounter: Option<Encounter>,
+}
+
+impl GameState {
+    pub fn wards(&self, target: ServerTarget) -> Option<&Vec<InstanceId>> {
+        match target {
+            ServerTarget::HandLair => Some(&self.sanctum.hand_lair),
+            ServerTarget::DeckLair => Some(&self.sanctum.deck_lair),
+            ServerTarget::DiscardLair => Some(&self.sanctum.discard_lair),
+            ServerTarget::Remote(index) => self.sanctum.remotes.get(index).map(|r| &r.wards),
+        }
+    }
+
+    pub(crate) fn start_infiltration(
+        &mut self,
+        faction: Faction,
+        target: ServerTarget,
+    ) -> Result<Vec<Update>, ActionError> {
+        if faction != Faction::Thief {
+            return Err(ActionError::WrongFaction);
+        }
+        // Wards are approached from the outermost one inward.
+        let step = match self.wards(target).ok_or(ActionError::UnknownServer)?.len() {
+            0 => InfiltrationStep::ApproachServer,
+            n => InfiltrationStep::ApproachWard(n - 1),
+        };
+        self.infiltration = Some(Infiltration {
+            target,
+            step,
+            to_access: vec![],
+            encounter: None,
+        });
+        Ok(vec![self.infiltration_update()])
+    }
+
+    pub(crate) fn handle_infiltration_action(
+        &mut self,
+        action: &ActionReq,
+    ) -> Result<Vec<Update>, ActionError> {
+        let Some(infiltration) = &self.infiltration else {
+            return Err(ActionError::NotInfiltrating);
+        };
+        let target = infiltration.target;
+
+        match (infiltration.step, action) {
+            (
+                InfiltrationStep::ApproachWard(_) | InfiltrationStep::ApproachServer,
+                ActionReq::JackOut,
+            ) => Ok(self.end_infiltration(false)),
+            (InfiltrationStep::ApproachWard(position), ActionReq::Continue) => {
+                let ward = self.wards(target).unwrap()[position];
+                if self.is_rezzed(ward) {
+                    Ok(self.begin_encounter(position))
+                } else {
+                    Ok(self.set_infiltration_step(InfiltrationStep::RezWindow(position)))
+                }
+            }
+            (InfiltrationStep::RezWindow(position), ActionReq::Rez(instance_id)) => {
+                if self.wards(target).unwrap()[position] != *instance_id {
+                    return Err(ActionError::CannotRez);
+                }
+                let mut updates = self.rez(*instance_id)?;
+                updates.extend(self.begin_encounter(position));
+                Ok(updates)
+            }
+            (InfiltrationStep::RezWindow(position), ActionReq::Continue) => {
+                Ok(self.pass_ward(position))
+            }
+            (InfiltrationStep::EncounterWard(_), ActionReq::PumpStrength(counterspell)) => {
+                self.pump_strength(*counterspell)
+            }
+            (
+                InfiltrationStep::EncounterWard(_),
+                ActionReq::BreakSubroutines {
+                    counterspell,
+                    subroutines,
+                },
+            ) => self.break_subroutines(*counterspell, subroutines),
+            (InfiltrationStep::EncounterWard(position), ActionReq::Continue) => {
+                Ok(self.resolve_encounter(position))
+            }
+            (InfiltrationStep::ApproachServer, ActionReq::Continue) => Ok(self.begin_access()),
+            (InfiltrationStep::Access, ActionReq::Continue) => Ok(self.access_next()),
+            _ => Err(ActionError::UnsupportedAction),
+        }
+    }
+
+    fn infiltration_update(&self) -> Update {
+        let infiltration = self.infiltration.as_ref().unwrap();
+        Update::Public(Response::Infiltration {
+            target: infiltration.target,
+            step: infiltration.step,
+        })
+    }
+
+    pub(crate) fn set_infiltration_step(&mut self, step: InfiltrationStep) -> Vec<Update> {
+        self.infiltration.as_mut().unwrap().step = step;
+        vec![self.infiltration_update()]
+    }
+
+    pub(crate) fn pass_ward(&mut self, position: usize) -> Vec<Update> {
+        match position {
+            0 => self.set_infiltration_step(InfiltrationStep::ApproachServer),
+            _ => self.set_infiltration_step(InfiltrationStep::ApproachWard(position - 1)),
+        }
+    }
+
+    fn begin_access(&mut self) -> Vec<Update> {
+        let target = self.infiltration.as_ref().unwrap().target;
+        let mut to_access = match target {
+            ServerTarget::HandLair if !self.sanctum.hand.is_empty() => {
+                let index = self.rng.random_range(0..self.sanctum.hand.len());
+                vec![self.sanctum.hand[index]]
+            }
+            ServerTarget::HandLair => vec![],
+            ServerTarget::DeckLair => self.sanctum.deck.last().copied().into_iter().collect(),
+            ServerTarget::DiscardLair => self.sanctum.discard.clone(),
+            ServerTarget::Remote(index) => {
+                self.sanctum.remotes[index].contents.into_iter().collect()
+            }
+        };
+        to_access.reverse();
+        self.infiltration.as_mut().unwrap().to_access = to_access;
+
+        let mut updates = self.set_infiltration_step(InfiltrationStep::Access);
+        updates.extend(self.access_next());
+        updates
+    }
+
+    fn access_next(&mut self) -> Vec<Update> {
+        match self.infiltration.as_mut().unwrap().to_access.pop() {
+            Some(instance_id) => {
+                let mut updates = vec![Update::Public(Response::Access {
+                    card: self.instances.create_card_state(instance_id, true),
+                })];
+                updates.extend(self.steal_artifact(instance_id));
+                updates
+            }
+            None => self.end_infiltration(true),
+        }
+    }
+
+    pub(crate) fn end_infiltration(&mut self, successful: bool) -> Vec<Update> {
+        let infiltration = self.infiltration.take().unwrap();
+        vec![Update::Public(Response::InfiltrationEnded {
+            target: infiltration.target,
+            successful,
+        })]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::card::{CardType, Effect, Mana, WardSubType};
+    use common::rules::THIEF_STAMINA;
+
+    use super::*;
+    use crate::instances::InstantiateLocation;
+    use crate::testing::{card, game, instance, turn};
+
+    /// The Thief's turn, with `wards` face-down unrezzed wards on the hand lair.
+    fn infiltration_game(wards: usize) -> (GameState, Vec<InstanceId>) {
+        let mut game = game(vec![
+            card(
+                "ward",
+                Faction::Sanctum,
+                CardType::Ward {
+                    subtype: WardSubType::Glyph,
+                    cost: Mana(0),
+                    strength: 0,
+                    subroutines: vec![Effect::EndInfiltration],
+                },
+            ),
+            card("event", Faction::Thief, CardType::Event { cost: Mana(0) }),
+        ]);
+        for _ in 0..wards {
+            let ward = instance(&mut game, "ward", InstantiateLocation::Board(true));
+            game.sanctum.hand_lair.push(ward);
+        }
+        turn(&mut game, Faction::Thief);
+        let wards = game.sanctum.hand_lair.clone();
+        (game, wards)
+    }
+
+    fn step(game: &GameState) -> Option<InfiltrationStep> {
+        game.infiltration
+            .as_ref()
+            .map(|infiltration| infiltration.step)
+    }
+
+    #[test]
+    fn an_unprotected_lair_is_accessed_straight_away() {
+        let (mut game, _) = infiltration_game(0);
+        let top = *game.sanctum.deck.last().unwrap();
+        game.handle_action(
+            Faction::Thief,
+            &ActionReq::Infiltrate(ServerTarget::DeckLair),
+        )
+        .unwrap();
+        assert_eq!(step(&game), Some(InfiltrationStep::ApproachServer));
+        assert_eq!(game.thief.stats.stamina, THIEF_STAMINA - 1);
+
+        let updates = game
+            .handle_action(Faction::Thief, &ActionReq::Continue)
+            .unwrap();
+        assert!(updates.iter().any(|update| matches!(update,
+            Update::Public(Response::Access { card }) if card.get_instance_id() == top)));
+        assert_eq!(step(&game), Some(InfiltrationStep::Access));
+
+        let updates = game
+            .handle_action(Faction::Thief, &ActionReq::Continue)
+            .unwrap();
+        assert!(updates.iter().any(|update| matches!(
+            update,
+            Update::Public(Response::InfiltrationEnded {
+                successful: true,
+                ..
+            })
+        )));
+        assert!(game.infiltration.is_none());
+        // Accessing a card that isn't an artifact leaves it where it was.
+        assert_eq!(game.sanctum.deck.last(), Some(&top));
+    }
+
+    #[test]
+    fn wards_are_approached_from_the_outermost_in() {
+        let (mut game, wards) = infiltration_game(2);
+        game.handle_action(
+            Faction::Thief,
+            &ActionReq::Infiltrate(ServerTarget::HandLair),
+        )
+        .unwrap();
+        assert_eq!(step(&game), Some(InfiltrationStep::ApproachWard(1)));
+
+        // An unrezzed ward gives the Sanctum the chance to rez it.
+        game.handle_action(Faction::Thief, &ActionReq::Continue)
+            .unwrap();
+        assert_eq!(step(&game), Some(InfiltrationStep::RezWindow(1)));
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::Continue)
+                .err(),
+            Some(ActionError::NotYourTurn)
+        );
+        game.handle_action(Faction::Sanctum, &ActionReq::Continue)
+            .unwrap();
+        assert_eq!(step(&game), Some(InfiltrationStep::ApproachWard(0)));
+
+        game.handle_action(Faction::Thief, &ActionReq::Continue)
+            .unwrap();
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::Rez(wards[1]))
+                .err(),
+            Some(ActionError::CannotRez)
+        );
+        game.handle_action(Faction::Sanctum, &ActionReq::Rez(wards[0]))
+            .unwrap();
+        assert_eq!(step(&game), Some(InfiltrationStep::EncounterWard(0)));
+        assert!(game.is_rezzed(wards[0]));
+    }
+
+    #[test]
+    fn the_thief_can_only_jack_out_while_approaching() {
+        let (mut game, _) = infiltration_game(1);
+        game.handle_action(
+            Faction::Thief,
+            &ActionReq::Infiltrate(ServerTarget::HandLair),
+        )
+        .unwrap();
+        game.handle_action(Faction::Thief, &ActionReq::JackOut)
+            .unwrap();
+        assert!(game.infiltration.is_none());
+
+        game.handle_action(
+            Faction::Thief,
+            &ActionReq::Infiltrate(ServerTarget::DeckLair),
+        )
+        .unwrap();
+        game.handle_action(Faction::Thief, &ActionReq::Continue)
+            .unwrap();
+        assert_eq!(step(&game), Some(InfiltrationStep::Access));
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::JackOut)
+                .err(),
+            Some(ActionError::UnsupportedAction)
+        );
+    }
+
+    #[test]
+    fn infiltrations_are_refused_when_they_cant_start() {
+        let (mut game, _) = infiltration_game(0);
+        assert_eq!(
+            game.handle_action(
+                Faction::Thief,
+                &ActionReq::Infiltrate(ServerTarget::Remote(0))
+            )
+            .err(),
+            Some(ActionError::UnknownServer)
+        );
+        assert_eq!(game.thief.stats.stamina, THIEF_STAMINA);
+
+        game.handle_action(
+            Faction::Thief,
+            &ActionReq::Infiltrate(ServerTarget::DeckLair),
+        )
+        .unwrap();
+        for action in [
+            ActionReq::Infiltrate(ServerTarget::HandLair),
+            ActionReq::GainMana,
+        ] {
+            assert_eq!(
+                game.handle_action(Faction::Thief, &action).err(),
+                Some(ActionError::InfiltrationInProgress)
+            );
+        }
+
+        let (mut game, _) = infiltration_game(0);
+        turn(&mut game, Faction::Sanctum);
+        assert_eq!(
+            game.handle_action(
+                Faction::Sanctum,
+                &ActionReq::Infiltrate(ServerTarget::DeckLair)
+            )
+            .err(),
+            Some(ActionError::WrongFaction)
+        );
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::Continue)
+                .err(),
+            Some(ActionError::NotInfiltrating)
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use common::card::{CardType, Faction};
+use common::rules::{FOCUS_CAPACITY, WARD_TAX};
+use common::{InstanceId, Remote, Response, ServerTarget};
+
+use crate::game_state::{ActionError, GameState, Update};
+use crate::instances::InstantiateLocation;
+
+impl GameState {
+    fn wards_mut(&mut self, target: ServerTarget) -> &mut Vec<InstanceId> {
+        match target {
+            ServerTarget::HandLair => &mut self.sanctum.hand_lair,
+            ServerTarget::DeckLair => &mut self.sanctum.deck_lair,
+            ServerTarget::DiscardLair => &mut self.sanctum.discard_lair,
+            ServerTarget::Remote(index) => &mut self.sanctum.remotes[index].wards,
+        }
+    }
+
+    /// Installs a card from the Sanctum's hand face-down. Wards go outside the
+    /// ones already protecting `server`, everything else into an empty remote.
+    pub(crate) fn install(
+        &mut self,
+        faction: Faction,
+        instance_id: InstanceId,
+        server: ServerTarget,
+    ) -> Result<Vec<Update>, ActionError> {
+        if faction != Faction::Sanctum {
+            return Err(ActionError::WrongFaction);
+        }
+        let Some(index) = self.sanctum.hand.iter().position(|&id| id == instance_id) else {
+            return Err(ActionError::NotInHand);
+        };
+        let is_ward = match self.card_data(instance_id).data {
+            CardType::Ward { .. } => true,
+            CardType::Asset { .. } | CardType::AncientArtifact { .. } => false,
+            _ => return Err(ActionError::CannotInstall),
+        };
+        let new_remote = server == ServerTarget::Remote(self.sanctum.remotes.len());
+        if !new_remote && self.wards(server).is_none() {
+            return Err(ActionError::UnknownServer);
+        }
+        if is_ward {
+            let wards = self.wards(server).map_or(0, |wards| wards.len() as u32);
+            self.pay_mana(Faction::Sanctum, wards * WARD_TAX)?;
+        } else {
+            match server {
+                ServerTarget::Remote(index) => {
+                    if self
+                        .sanctum
+                        .remotes
+                        .get(index)
+                        .is_some_and(|remote| remote.contents.is_some())
+                    {
+                        return Err(ActionError::ServerOccupied);
+                    }
+                }
+                _ => return Err(ActionError::CannotInstall),
+            }
+        }
+
+        self.sanctum.hand.remove(index);
+        let installed =
+            self.instances
+                .move_to(instance_id, InstantiateLocation::Board(true), &mut self.rng);
+        if new_remote {
+            self.sanctum.remotes.push(Remote {
+                wards: vec![],
+                contents: None,
+            });
+        }
+        match (is_ward, server) {
+            (true, _) => self.wards_mut(server).push(installed),
+            (false, ServerTarget::Remote(index)) => {
+                self.sanctum.remotes[index].contents = Some(installed)
+            }
+            (false, _) => unreachable!(),
+        }
+
+        Ok(vec![Update::Private {
+            owner: Faction::Sanctum,
+            owner_view: Response::Installed {
+                card: self.instances.create_card_state(installed, true),
+                server,
+                ward: is_ward,
+                from_hand: Some(instance_id),
+            },
+            other_view: Response::Installed {
+                card: self.instances.create_card_state(installed, false),
+                server,
+                ward: is_ward,
+                from_hand: None,
+            },
+        }])
+    }
+
+    /// Focus taken up by the Thief's installed counterspells.
+    pub fn focus_used(&self) -> u32 {
+        self.thief
+            .spell_slots
+            .iter()
+            .map(|&instance_id| match self.card_data(instance_id).data {
+                CardType::CounterSpell { focus_cost, .. } => focus_cost,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Installs a card from the Thief's hand face-up into the slot for its type.
+    pub(crate) fn install_rig(
+        &mut self,
+        faction: Faction,
+        instance_id: InstanceId,
+    ) -> Result<Vec<Update>, ActionError> {
+        if faction != Faction::Thief {
+            return Err(ActionError::WrongFaction);
+        }
+        let Some(index) = self.thief.hand.iter().position(|&id| id == instance_id) else {
+            return Err(ActionError::NotInHand);
+        };
+        let data = self.card_data(instance_id).data.clone();
+        match data {
+            CardType::CounterSpell { focus_cost, .. } => {
+                if self.focus_used() + focus_cost > FOCUS_CAPACITY {
+                    return Err(ActionError::NotEnoughFocus);
+                }
+            }
+            CardType::MagicalGear | CardType::Ally => (),
+            _ => return Err(ActionError::CannotInstall),
+        }
+        self.pay_mana(Faction::Thief, data.cost().unwrap_or(0))?;
+
+        self.thief.hand.remove(index);
+        let installed = self.instances.move_to(
+            instance_id,
+            InstantiateLocation::Board(false),
+            &mut self.rng,
+        );
+        match data {
+            CardType::CounterSpell { .. } => self.thief.spell_slots.push(installed),
+            CardType::MagicalGear => self.thief.gear_slots.push(installed),
+            _ => self.thief.ally_slots.push(installed),
+        }
+
+        let card = self.instances.create_card_state(installed, true);
+        Ok(vec![Update::Private {
+            owner: Faction::Thief,
+            owner_view: Response::RigInstalled {
+                card: card.clone(),
+                from_hand: Some(instance_id),
+            },
+            other_view: Response::RigInstalled {
+                card,
+                from_hand: None,
+            },
+        }])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::ActionReq;
+    use common::card::{CardState, CounterSpellSubType, Effect, Mana, PaidAbility, WardSubType};
+    use common::rules::{SANCTUM_STAMINA, STARTING_MANA, THIEF_STAMINA};
+
+    use super::*;
+    use crate::testing::{card, game, in_hand, turn};
+
+    fn install_game() -> GameState {
+        game(vec![
+            card(
+                "ward",
+                Faction::Sanctum,
+                CardType::Ward {
+                    subtype: WardSubType::Glyph,
+                    cost: Mana(3),
+                    strength: 1,
+                    subroutines: vec![Effect::EndInfiltration],
+                },
+            ),
+            card(
+                "artifact",
+                Faction::Sanctum,
+                CardType::AncientArtifact {
+                    vp: 2,
+                    attunement: 2,
+                },
+            ),
+            card(
+                "operation",
+                Faction::Sanctum,
+                CardType::Operation {
+                    subtype: None,
+                    cost: Mana(0),
+                },
+            ),
+            card("event", Faction::Thief, CardType::Event { cost: Mana(0) }),
+            card(
+                "spell",
+                Faction::Thief,
+                CardType::CounterSpell {
+                    subtype: CounterSpellSubType::Fracter,
+                    cost: Mana(2),
+                    strength: 1,
+                    focus_cost: FOCUS_CAPACITY - 1,
+                    pump: None,
+                    break_subroutines: PaidAbility {
+                        cost: Mana(1),
+                        amount: 1,
+                    },
+                },
+            ),
+            card("gear", Faction::Thief, CardType::MagicalGear),
+            card("ally", Faction::Thief, CardType::Ally),
+        ])
+    }
+
+    fn install(
+        game: &mut GameState,
+        card: InstanceId,
+        server: ServerTarget,
+    ) -> Result<Vec<Update>, ActionError> {
+        game.handle_action(Faction::Sanctum, &ActionReq::Install { card, server })
+    }
+
+    #[test]
+    fn wards_go_outside_the_others_and_pay_for_each() {
+        let mut game = install_game();
+        let first = in_hand(&mut game, Faction::Sanctum, "ward");
+        let second = in_hand(&mut game, Faction::Sanctum, "ward");
+        install(&mut game, first, ServerTarget::HandLair).unwrap();
+        // Installing is free of the rez cost, and the first ward of the tax.
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA);
+        let updates = install(&mut game, second, ServerTarget::HandLair).unwrap();
+        assert_eq!(game.sanctum.stats.mana_pool, STARTING_MANA - WARD_TAX);
+
+        assert!(!game.sanctum.hand.contains(&first));
+        assert!(!game.sanctum.hand.contains(&second));
+        let outermost = game.sanctum.hand_lair[1];
+        assert!(!game.is_rezzed(outermost));
+        let Update::Private {
+            owner_view:
+                Response::Installed {
+                    card: mine,
+                    from_hand,
+                    ..
+                },
+            other_view:
+                Response::Installed {
+                    card: theirs,
+                    from_hand: None,
+                    ..
+                },
+            ..
+        } = &updates[0]
+        else {
+            panic!("not a private install: {:?}", updates[0]);
+        };
+        assert_eq!(*from_hand, Some(second));
+        assert!(matches!(mine, CardState::Revealed(id, ..) if *id == outermost));
+        assert!(matches!(theirs, CardState::Hidden(id, _) if *id == outermost));
+    }
+
+    #[test]
+    fn artifacts_open_a_remote_of_their_own() {
+        let mut game = install_game();
+        let first = in_hand(&mut game, Faction::Sanctum, "artifact");
+        let second = in_hand(&mut game, Faction::Sanctum, "artifact");
+        assert_eq!(
+            install(&mut game, first, ServerTarget::Remote(1)).err(),
+            Some(ActionError::UnknownServer)
+        );
+        assert_eq!(
+            install(&mut game, first, ServerTarget::HandLair).err(),
+            Some(ActionError::CannotInstall)
+        );
+        install(&mut game, first, ServerTarget::Remote(0)).unwrap();
+        assert_eq!(game.sanctum.remotes.len(), 1);
+        assert!(game.sanctum.remotes[0].contents.is_some());
+        assert_eq!(
+            install(&mut game, second, ServerTarget::Remote(0)).err(),
+            Some(ActionError::ServerOccupied)
+        );
+        assert!(game.sanctum.hand.contains(&second));
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA - 1);
+    }
+
+    #[test]
+    fn only_installable_cards_in_hand_are_installed() {
+        let mut game = install_game();
+        let operation = in_hand(&mut game, Faction::Sanctum, "operation");
+        assert_eq!(
+            install(&mut game, operation, ServerTarget::Remote(0)).err(),
+            Some(ActionError::CannotInstall)
+        );
+        let in_deck = *game.sanctum.deck.last().unwrap();
+        assert_eq!(
+            install(&mut game, in_deck, ServerTarget::HandLair).err(),
+            Some(ActionError::NotInHand)
+        );
+
+        let ward = in_hand(&mut game, Faction::Sanctum, "ward");
+        let protected = in_hand(&mut game, Faction::Sanctum, "ward");
+        install(&mut game, protected, ServerTarget::DeckLair).unwrap();
+        game.sanctum.stats.mana_pool = 0;
+        assert_eq!(
+            install(&mut game, ward, ServerTarget::DeckLair).err(),
+            Some(ActionError::NotEnoughMana)
+        );
+        assert!(game.sanctum.hand.contains(&ward));
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA - 1);
+    }
+
+    #[test]
+    fn rig_cards_go_face_up_into_their_slots() {
+        let mut game = install_game();
+        turn(&mut game, Faction::Thief);
+        let spell = in_hand(&mut game, Faction::Thief, "spell");
+        let gear = in_hand(&mut game, Faction::Thief, "gear");
+        let ally = in_hand(&mut game, Faction::Thief, "ally");
+        for card in [spell, gear, ally] {
+            game.handle_action(Faction::Thief, &ActionReq::InstallRig(card))
+                .unwrap();
+            assert!(!game.thief.hand.contains(&card));
+        }
+        assert_eq!(game.thief.stats.mana_pool, STARTING_MANA - 2);
+        assert_eq!(game.focus_used(), FOCUS_CAPACITY - 1);
+        for slots in [
+            &game.thief.spell_slots,
+            &game.thief.gear_slots,
+            &game.thief.ally_slots,
+        ] {
+            assert_eq!(slots.len(), 1);
+            assert!(game.is_rezzed(slots[0]));
+        }
+    }
+
+    #[test]
+    fn counterspells_need_focus_and_mana() {
+        let mut game = install_game();
+        turn(&mut game, Faction::Thief);
+        let first = in_hand(&mut game, Faction::Thief, "spell");
+        let second = in_hand(&mut game, Faction::Thief, "spell");
+        game.thief.stats.mana_pool = 1;
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::InstallRig(first))
+                .err(),
+            Some(ActionError::NotEnoughMana)
+        );
+        game.thief.stats.mana_pool = 10;
+        game.handle_action(Faction::Thief, &ActionReq::InstallRig(first))
+            .unwrap();
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::InstallRig(second))
+                .err(),
+            Some(ActionError::NotEnoughFocus)
+        );
+        assert!(game.thief.hand.contains(&second));
+        assert_eq!(game.thief.stats.mana_pool, 8);
+        assert_eq!(game.thief.stats.stamina, THIEF_STAMINA - 1);
+    }
+
+    #[test]
+    fn only_rig_cards_are_installed_in_the_rig() {
+        let mut game = install_game();
+        let ward = in_hand(&mut game, Faction::Sanctum, "ward");
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::InstallRig(ward))
+                .err(),
+            Some(ActionError::WrongFaction)
+        );
+        turn(&mut game, Faction::Thief);
+        let event = in_hand(&mut game, Faction::Thief, "event");
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::InstallRig(event))
+                .err(),
+            Some(ActionError::CannotInstall)
+        );
+        let in_deck = *game.thief.deck.last().unwrap();
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::InstallRig(in_deck))
+                .err(),
+            Some(ActionError::NotInHand)
+        );
+        assert_eq!(game.thief.stats.stamina, THIEF_STAMINA);
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use common::card::{CardState, Counters};
+use common::{CardId, InstanceId};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+type IsHidden = bool;
+#[derive(Serialize, Deserialize)]
+pub enum InstantiateLocation {
+    Board(IsHidden),
+    Hand,
+    Deck,
+    Trash,
+    ScoreArea,
+}
+#[derive(Serialize, Deserialize)]
+pub struct InstantiatedCard {
+    pub id: CardId,
+    pub location: InstantiateLocation,
+    pub counters: Counters,
+}
+/// Instance ids are random and a card gets a new one whenever it changes zones,
+/// so clients can't follow a card through hidden zones by its id.
+#[derive(Serialize, Deserialize)]
+pub struct Instances {
+    pub data: HashMap<InstanceId, InstantiatedCard>,
+}
+impl Default for Instances {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Instances {
+    pub fn new() -> Self {
+        Self {
+            data: HashMap::new(),
+        }
+    }
+    fn fresh_id(&self, rng: &mut impl Rng) -> InstanceId {
+        loop {
+            let instance_id = rng.random_range(1..=InstanceId::MAX);
+            if !self.data.contains_key(&instance_id) {
+                return instance_id;
+            }
+        }
+    }
+    pub fn create_instance(
+        &mut self,
+        card_id: &CardId,
+        location: InstantiateLocation,
+        rng: &mut impl Rng,
+    ) -> InstanceId {
+        let instance_id = self.fresh_id(rng);
+        self.data.insert(
+            instance_id,
+            InstantiatedCard {
+                id: card_id.clone(),
+                location,
+                counters: Counters::new(),
+            },
+        );
+        instance_id
+    }
+    /// Moves a card to `location` under a fresh id, the old id stops existing.
+    /// Counters don't follow a card to another zone.
+    pub fn move_to(
+        &mut self,
+        instance_id: InstanceId,
+        location: InstantiateLocation,
+        rng: &mut impl Rng,
+    ) -> InstanceId {
+        let mut card = self.data.remove(&instance_id).unwrap();
+        card.location = location;
+        card.counters.clear();
+        let new_id = self.fresh_id(rng);
+        self.data.insert(new_id, card);
+        new_id
+    }
+    pub fn get(&self, instance_id: InstanceId) -> Option<&InstantiatedCard> {
+        self.data.get(&instance_id)
+    }
+    pub fn get_instantiated_card(&self, instance_id: InstanceId) -> &InstantiatedCard {
+        self.data.get(&instance_id).unwrap()
+    }
+    pub fn get_mut_instantiated_card(&mut self, instance_id: InstanceId) -> &mut InstantiatedCard {
+        self.data.get_mut(&instance_id).unwrap()
+    }
+    pub fn create_card_state(&self, instance_id: InstanceId, visible: bool) -> CardState {
+        let card = self.get_instantiated_card(instance_id);
+        if visible {
+            CardState::Revealed(instance_id, card.id.clone(), card.counters.clone())
+        } else {
+            CardState::Hidden(instance_id, card.counters.clone())
+        }
+    }
+    pub fn create_card_states(&self, instances: &[InstanceId], visible: bool) -> Vec<CardState> {
+        let cards: Vec<CardState> = instances
+            .iter()
+            .map(|&instance_id| self.create_card_state(instance_id, visible))
+            .collect();
+        cards
+    }
+}
@@ -0,0 +1,16 @@
+pub mod attunement;
+pub mod counters;
+pub mod deck;
+pub mod effects;
+pub mod encounter;
+pub mod game_state;
+pub mod infiltration;
+pub mod install;
+pub mod instances;
+pub mod lobby;
+pub mod play;
+pub mod replay;
+pub mod snapshot;
+pub mod spectators;
+#[cfg(test)]
+mod testing;
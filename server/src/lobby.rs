@@ -0,0 +1,250 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use common::card::{CardData, Faction};
+use common::deck::{DeckChoice, DeckList};
+use common::replay::GameEvent;
+use common::{CardId, GameOverReason, SessionToken, TableInfo};
+use serde::{Deserialize, Serialize};
+
+use crate::deck::{DeckError, resolve_deck, validate_deck};
+use crate::game_state::{GameState, other_faction};
+
+/// How long a dropped player's seat is held for them to resume.
+pub const RECONNECT_GRACE: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LobbyError {
+    TableExists(String),
+    UnknownTable(String),
+    SeatTaken(Faction),
+    UnknownSession,
+    Deck(DeckError),
+}
+impl fmt::Display for LobbyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LobbyError::TableExists(name) => write!(f, "there's already a table named {}", name),
+            LobbyError::UnknownTable(name) => write!(f, "no table named {}", name),
+            LobbyError::SeatTaken(faction) => write!(f, "the {} seat is taken", faction),
+            LobbyError::UnknownSession => write!(f, "that session has expired"),
+            LobbyError::Deck(err) => write!(f, "{}", err),
+        }
+    }
+}
+impl From<DeckError> for LobbyError {
+    fn from(err: DeckError) -> Self {
+        LobbyError::Deck(err)
+    }
+}
+
+/// A player sitting at a table with the deck they brought. The token lets them
+/// take the seat back after losing their connection.
+#[derive(Serialize, Deserialize)]
+pub struct Seat {
+    pub player: String,
+    pub deck: DeckList,
+    pub token: SessionToken,
+    #[serde(skip)]
+    pub disconnected_at: Option<Instant>,
+}
+impl Seat {
+    /// Connected, or dropped but still within the grace period.
+    fn is_held(&self, now: Instant) -> bool {
+        self.disconnected_at
+            .is_none_or(|since| now.duration_since(since) < RECONNECT_GRACE)
+    }
+}
+
+/// Where a resumed session sits.
+pub struct Session {
+    pub table: String,
+    pub faction: Faction,
+    pub player: String,
+}
+
+/// One Sanctum and one Thief playing their own match. The game starts as soon
+/// as both seats are taken.
+#[derive(Serialize, Deserialize)]
+pub struct Table {
+    pub seed: u64,
+    pub sanctum: Option<Seat>,
+    pub thief: Option<Seat>,
+    pub game: Option<GameState>,
+    /// Where the game is being recorded, so a restored server carries on in the same file.
+    #[serde(default)]
+    pub replay: Option<PathBuf>,
+}
+impl Table {
+    pub fn seat(&self, faction: Faction) -> &Option<Seat> {
+        match faction {
+            Faction::Sanctum => &self.sanctum,
+            Faction::Thief => &self.thief,
+        }
+    }
+    fn seat_mut(&mut self, faction: Faction) -> &mut Option<Seat> {
+        match faction {
+            Faction::Sanctum => &mut self.sanctum,
+            Faction::Thief => &mut self.thief,
+        }
+    }
+}
+
+pub struct Lobby {
+    cards: HashMap<CardId, CardData>,
+    pub tables: BTreeMap<String, Table>,
+}
+impl Lobby {
+    pub fn new(cards: HashMap<CardId, CardData>) -> Self {
+        Self {
+            cards,
+            tables: BTreeMap::new(),
+        }
+    }
+
+    /// Picks up the tables of a snapshot. Nobody is connected yet, so every seat
+    /// starts its grace period now and is given up like any other dropped seat
+    /// if its player doesn't resume in time.
+    pub fn restore(cards: HashMap<CardId, CardData>, mut tables: BTreeMap<String, Table>) -> Self {
+        let now = Instant::now();
+        for table in tables.values_mut() {
+            for seat in [&mut table.sanctum, &mut table.thief].into_iter().flatten() {
+                seat.disconnected_at = Some(now);
+            }
+        }
+        Self { cards, tables }
+    }
+
+    pub fn create_table(&mut self, name: &str, seed: u64) -> Result<(), LobbyError> {
+        if self.tables.contains_key(name) {
+            return Err(LobbyError::TableExists(name.to_string()));
+        }
+        self.tables.insert(
+            name.to_string(),
+            Table {
+                seed,
+                sanctum: None,
+                thief: None,
+                game: None,
+                replay: None,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn table_infos(&self) -> Vec<TableInfo> {
+        self.tables
+            .iter()
+            .map(|(name, table)| TableInfo {
+                name: name.clone(),
+                sanctum: table.sanctum.as_ref().map(|seat| seat.player.clone()),
+                thief: table.thief.as_ref().map(|seat| seat.player.clone()),
+                started: table.game.is_some(),
+            })
+            .collect()
+    }
+
+    /// Seats `player` at a table. Returns the session token for the seat, and
+    /// whether this completed the table and its game has just started.
+    pub fn join(
+        &mut self,
+        name: &str,
+        faction: Faction,
+        player: &str,
+        deck: &DeckChoice,
+    ) -> Result<(SessionToken, bool), LobbyError> {
+        let table = self
+            .tables
+            .get_mut(name)
+            .ok_or_else(|| LobbyError::UnknownTable(name.to_string()))?;
+        if table.seat(faction).is_some() {
+            return Err(LobbyError::SeatTaken(faction));
+        }
+        let deck = resolve_deck(deck)?;
+        validate_deck(faction, &deck, &self.cards)?;
+        let token = format!("{:032x}", rand::random::<u128>());
+        *table.seat_mut(faction) = Some(Seat {
+            player: player.to_string(),
+            deck,
+            token: token.clone(),
+            disconnected_at: None,
+        });
+
+        if let (Some(sanctum), Some(thief)) = (&table.sanctum, &table.thief) {
+            table.game = Some(GameState::new(
+                self.cards.clone(),
+                &sanctum.deck,
+                &thief.deck,
+                table.seed,
+            ));
+            return Ok((token, true));
+        }
+        Ok((token, false))
+    }
+
+    /// Gives a held seat back to the player holding its token.
+    pub fn resume(&mut self, token: &str) -> Result<Session, LobbyError> {
+        for (name, table) in self.tables.iter_mut() {
+            for faction in [Faction::Sanctum, Faction::Thief] {
+                if let Some(seat) = table.seat_mut(faction)
+                    && seat.token == token
+                {
+                    seat.disconnected_at = None;
+                    return Ok(Session {
+                        table: name.clone(),
+                        faction,
+                        player: seat.player.clone(),
+                    });
+                }
+            }
+        }
+        Err(LobbyError::UnknownSession)
+    }
+
+    /// Holds the seat of a player who dropped, see `expire_seats`.
+    pub fn leave(&mut self, name: &str, faction: Faction) {
+        if let Some(table) = self.tables.get_mut(name)
+            && let Some(seat) = table.seat_mut(faction)
+        {
+            seat.disconnected_at = Some(Instant::now());
+        }
+    }
+
+    /// Gives up the seats held for longer than `RECONNECT_GRACE`. Before the game
+    /// the seat is freed, during it the player forfeits. Started tables are closed
+    /// once nobody is left at them. Returns the forfeit of every game that ended.
+    pub fn expire_seats(&mut self, now: Instant) -> Vec<(String, GameEvent)> {
+        let mut forfeits = vec![];
+        for (name, table) in self.tables.iter_mut() {
+            for faction in [Faction::Sanctum, Faction::Thief] {
+                let expired = table
+                    .seat(faction)
+                    .as_ref()
+                    .is_some_and(|seat| !seat.is_held(now));
+                if !expired {
+                    continue;
+                }
+                match table.game.as_mut() {
+                    None => *table.seat_mut(faction) = None,
+                    Some(game) if !game.is_over() => forfeits.push((
+                        name.clone(),
+                        GameEvent::Forfeit {
+                            loser: faction,
+                            updates: vec![
+                                game.end_game(other_faction(faction), GameOverReason::Forfeit),
+                            ],
+                        },
+                    )),
+                    Some(_) => (),
+                }
+            }
+        }
+        self.tables.retain(|_, table| {
+            let held = |seat: &Option<Seat>| seat.as_ref().is_some_and(|seat| seat.is_held(now));
+            table.game.is_none() || held(&table.sanctum) || held(&table.thief)
+        });
+        forfeits
+    }
+}
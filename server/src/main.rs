@@ -1,237 +1,457 @@
 use std::collections::HashMap;
-use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use common::card::{CardData, CardState, Faction, load_cards_from_json};
+use common::args::arg_value;
+use common::card::load_cards_from_json;
 use common::player::Player;
-use common::{
-    ActionReq, BasicStats, CardId, CommonState, InitStateResponse, InstanceId, PlayerStateResponse,
-    PlayerType, Response, SanctumState, ThiefState,
-};
+use common::replay::GameEvent;
+use common::{ActionError, ActionReq, Response};
 use message_io::network::{Endpoint, NetEvent, Transport};
-use message_io::node::{self, NodeListener}; // <-- Using shared code!
-
-type IsHidden = bool;
-enum InstantiateLocation {
-    BOARD(IsHidden),
-    HAND,
-    DECK,
-    TRASH,
-}
-struct InstantiatedCard {
-    id: CardId,
-    location: InstantiateLocation,
+use message_io::node::{self, NodeEvent, NodeHandler};
+use server::game_state::{GameState, Update};
+use server::lobby::{Lobby, LobbyError, RECONNECT_GRACE};
+use server::replay::{GameLog, REPLAYS_DIRECTORY, replay_header};
+use server::snapshot::{load_snapshot, save_snapshot};
+use server::spectators::{DelayedFeed, SpectatorVisibility};
+
+enum Signal {
+    ExpireSeats,
+    FlushSpectators,
 }
-struct Instances {
-    counter: u32,
-    pub data: HashMap<InstanceId, InstantiatedCard>,
+
+struct Spectators {
+    visibility: SpectatorVisibility,
+    feed: DelayedFeed<Endpoint>,
 }
-impl Instances {
-    fn new() -> Self {
-        Self {
-            counter: 0,
-            data: HashMap::new(),
-        }
-    }
-    fn create_instance(&mut self, card_id: &CardId, location: InstantiateLocation) -> u32 {
-        self.counter += 1;
-        self.data.insert(
-            self.counter,
-            InstantiatedCard {
-                id: card_id.clone(),
-                location,
-            },
-        );
-        self.counter
-    }
-    fn get_instantiated_card(&self, instance_id: InstanceId) -> &InstantiatedCard {
-        self.data.get(&instance_id).unwrap()
-    }
-    fn get_mut_instantiated_card(&mut self, instance_id: InstanceId) -> &mut InstantiatedCard {
-        self.data.get_mut(&instance_id).unwrap()
-    }
-    fn create_card_state(&self, instance_id: InstanceId, visible: bool) -> CardState {
-        if visible {
-            CardState::Revealed(
-                instance_id,
-                self.get_instantiated_card(instance_id).id.clone(),
-            )
-        } else {
-            CardState::Hidden(instance_id)
+impl Spectators {
+    fn send(&mut self, handler: &NodeHandler<Signal>, client: Endpoint, response: &Response) {
+        match self.visibility {
+            SpectatorVisibility::Public => send(handler, client, response),
+            SpectatorVisibility::Delayed(delay) => {
+                self.feed.push(Instant::now(), client, response.clone());
+                handler
+                    .signals()
+                    .send_with_timer(Signal::FlushSpectators, delay);
+            }
         }
     }
-    fn create_card_states(&self, instances: &Vec<InstanceId>, visible: bool) -> Vec<CardState> {
-        let cards: Vec<CardState> = instances
-            .iter()
-            .map(|&instance_id| self.create_card_state(instance_id, visible))
-            .collect();
-        cards
+    fn send_state(&mut self, handler: &NodeHandler<Signal>, client: Endpoint, game: &GameState) {
+        let state = game.spectator_state_response(self.visibility.is_omniscient());
+        self.send(handler, client, &Response::Initial(state));
     }
 }
-fn instantiate_deck(
-    faction: Faction,
-    cards: &HashMap<String, CardData>,
-    instances: &mut Instances,
-) -> Vec<InstanceId> {
-    let mut deck: Vec<InstanceId> = vec![];
-    for i in 0..4 {
-        for data in cards.iter().filter(|data| data.1.faction == faction) {
-            deck.push(instances.create_instance(&data.1.id, InstantiateLocation::DECK));
+
+fn send(handler: &NodeHandler<Signal>, client: Endpoint, response: &Response) {
+    let bytes = bincode::serialize(response).unwrap();
+    handler.network().send(client, &bytes);
+}
+
+/// Sends everyone at `table`, players and spectators, their view of an update.
+fn broadcast(
+    handler: &NodeHandler<Signal>,
+    clients: &HashMap<Endpoint, Player>,
+    spectators: &mut Spectators,
+    table: &str,
+    update: &Update,
+) {
+    for (client, player) in clients.iter() {
+        if player.table.as_deref() != Some(table) {
+            continue;
+        }
+        match player.faction {
+            Some(faction) => send(handler, *client, update.view_for(faction)),
+            None => {
+                let omniscient = spectators.visibility.is_omniscient();
+                spectators.send(handler, *client, update.spectator_view(omniscient));
+            }
         }
     }
-    deck
 }
-fn create_hand(deck: &mut Vec<InstanceId>, instances: &mut Instances) -> Vec<InstanceId> {
-    let mut hand = vec![];
-    for i in (0..5) {
-        let ins = deck.pop().unwrap();
-        let card = instances.get_mut_instantiated_card(ins);
-        card.location = InstantiateLocation::HAND;
-        hand.push(ins);
-    }
 
-    hand
-}
-fn create_sanctum_state_response(
-    visible: bool,
-    instances: &Instances,
-    deck: &Vec<InstanceId>,
-    hand: &Vec<InstanceId>,
-) -> PlayerStateResponse {
-    PlayerStateResponse::Sanctum {
-        common: CommonState {
-            stats: BasicStats {
-                mana_pool: 5,
-                stamina: 5,
-                score: 0,
-            },
-            deck: instances.create_card_states(&deck, false),
-            hand: instances.create_card_states(&hand, visible),
-            discard: vec![],
-            score_area: vec![],
-        },
-        specific: SanctumState {
-            hand_lair: None,
-            deck_lair: None,
-            discard_lair: None,
-            remotes: None,
-        },
+fn record(logs: &mut HashMap<String, GameLog>, table: &str, event: &GameEvent) {
+    if let Some(log) = logs.get_mut(table)
+        && let Err(err) = log.append(event)
+    {
+        println!("Couldn't record to {}: {}", log.path.display(), err);
     }
 }
-fn create_thief_state_response(
-    visible: bool,
-    instances: &Instances,
-    deck: &Vec<InstanceId>,
-    hand: &Vec<InstanceId>,
-) -> PlayerStateResponse {
-    PlayerStateResponse::Thief {
-        common: CommonState {
-            stats: BasicStats {
-                mana_pool: 5,
-                stamina: 5,
-                score: 0,
-            },
-            deck: instances.create_card_states(&deck, false),
-            hand: instances.create_card_states(&hand, visible),
-            discard: vec![],
-            score_area: vec![],
-        },
-        specific: ThiefState {
-            spell_slots: None,
-            gear_slots: None,
-            ally_slots: None,
-        },
+
+/// Keeps the snapshot file, if there is one, in step with the lobby.
+fn persist(snapshot: Option<&Path>, lobby: &Lobby) {
+    if let Some(path) = snapshot
+        && let Err(err) = save_snapshot(path, lobby)
+    {
+        println!("Couldn't save the snapshot to {}: {}", path.display(), err);
     }
 }
+
 fn main() {
-    let cards = load_cards_from_json("cards.json").unwrap();
-    let mut instances = Instances::new();
-    let mut sanc_deck = instantiate_deck(Faction::Sanctum, &cards, &mut instances);
-    let sanc_hand = create_hand(&mut sanc_deck, &mut instances);
-    let mut thief_deck = instantiate_deck(Faction::Thief, &cards, &mut instances);
-    let thief_hand = create_hand(&mut thief_deck, &mut instances);
+    let args: Vec<String> = std::env::args().collect();
+    // Every table gets its own seed unless one is forced for all of them.
+    let forced_seed: Option<u64> =
+        arg_value(&args, "--seed").map(|seed| seed.parse().expect("--seed must be a number"));
+    let visibility = match arg_value(&args, "--spectators") {
+        Some(value) => SpectatorVisibility::parse(value)
+            .expect("--spectators must be public or delayed:<seconds>"),
+        None => SpectatorVisibility::Public,
+    };
+    let mut spectators = Spectators {
+        visibility,
+        feed: DelayedFeed::new(match visibility {
+            SpectatorVisibility::Delayed(delay) => delay,
+            SpectatorVisibility::Public => Duration::ZERO,
+        }),
+    };
+
+    let replays = PathBuf::from(arg_value(&args, "--replays").unwrap_or(REPLAYS_DIRECTORY));
+
+    // Saved after every change, and picked up again when the server restarts.
+    let snapshot = arg_value(&args, "--snapshot").map(PathBuf::from);
+
+    let cards = load_cards_from_json("cards.json").unwrap_or_else(|errors| {
+        eprint!("cards.json has problems:\n{}", errors);
+        std::process::exit(1);
+    });
+    // One replay file per running game, keyed by table.
+    let mut logs: HashMap<String, GameLog> = HashMap::new();
+    let mut lobby = match &snapshot {
+        Some(path) if path.exists() => {
+            let saved = load_snapshot(path)
+                .unwrap_or_else(|err| panic!("Couldn't load {}: {}", path.display(), err));
+            println!(
+                "Restored {} table(s) from {}",
+                saved.tables.len(),
+                path.display()
+            );
+            for (name, table) in saved.tables.iter() {
+                for seat in [&table.sanctum, &table.thief].into_iter().flatten() {
+                    // The token is theirs to present, it never goes in the log.
+                    println!("{} at {} can resume", seat.player, name);
+                }
+                if table.game.as_ref().is_some_and(|game| !game.is_over())
+                    && let Some(path) = &table.replay
+                {
+                    match GameLog::reopen(path) {
+                        Ok(log) => {
+                            logs.insert(name.clone(), log);
+                        }
+                        Err(err) => println!("Not recording {}: {}", name, err),
+                    }
+                }
+            }
+            Lobby::restore(cards, saved.tables)
+        }
+        _ => Lobby::new(cards),
+    };
 
     let mut clients: HashMap<Endpoint, Player> = HashMap::new();
-    let (node, listener) = node::split::<()>();
+    let (node, listener) = node::split::<Signal>();
 
     // Start listening for WebSocket connections
     node.network()
         .listen(Transport::Ws, "0.0.0.0:8080")
         .unwrap();
     println!("Server running on ws://0.0.0.0:8080");
+    // Bots connect over framed TCP, see SERVER_ADDRESS in the bot.
+    node.network()
+        .listen(Transport::FramedTcp, "0.0.0.0:8081")
+        .unwrap();
+    println!("Bots can connect on tcp://0.0.0.0:8081");
 
-    // A simple way to store all your game states
-
-    listener.for_each(move |event| match event.network() {
-        NetEvent::Connected(_, _) => (), // Only generated at connect() calls.
-        NetEvent::Accepted(endpoint, _listener_id) => {
+    listener.for_each(move |event| match event {
+        NodeEvent::Signal(Signal::ExpireSeats) => {
+            for (table, event) in lobby.expire_seats(Instant::now()) {
+                println!("A player at {} didn't come back in time", table);
+                for update in event.updates() {
+                    broadcast(&node, &clients, &mut spectators, &table, update);
+                }
+                record(&mut logs, &table, &event);
+            }
+            logs.retain(|table, _| lobby.tables.contains_key(table));
+            persist(snapshot.as_deref(), &lobby);
+        }
+        NodeEvent::Signal(Signal::FlushSpectators) => {
+            for (client, response) in spectators.feed.pop_due(Instant::now()) {
+                send(&node, client, &response);
+            }
+        }
+        NodeEvent::Network(NetEvent::Connected(_, _)) => (), // Only generated at connect() calls.
+        NodeEvent::Network(NetEvent::Accepted(endpoint, _listener_id)) => {
             // Only connection oriented protocols will generate this event
             clients.insert(
                 endpoint,
                 Player {
                     id: None,
                     faction: None,
+                    table: None,
                 },
             );
             println!("Client ({}) connected", endpoint.addr());
         }
-        NetEvent::Message(endpoint, input_data) => {
-            let message: ActionReq = bincode::deserialize(&input_data).unwrap();
+        NodeEvent::Network(NetEvent::Message(endpoint, input_data)) => {
+            let reject = |reason: ActionError| {
+                send(&node, endpoint, &Response::ActionRejected { reason });
+            };
+            let Ok(message) = bincode::deserialize::<ActionReq>(input_data) else {
+                println!("Client ({}) sent a malformed request", endpoint.addr());
+                reject(ActionError::MalformedRequest);
+                return;
+            };
 
             match message {
-                ActionReq::DrawCard(id) => {
-
-                    let visible =  instances.create_card_state(id,true);
-                                       let output_data = bincode::serialize(&Response::DrawCard { card:visible }).unwrap();
-                                      node.network().send(endpoint, &output_data);
-                     for client in clients.iter().filter(|&(e,_)| *e != endpoint)
-                     {
-                        let invisible =  instances.create_card_state(id,false);
-                         let output_data = bincode::serialize(&Response::DrawCard { card:invisible }).unwrap();
-                         node.network().send(*client.0, &output_data);
-                     }
-
-                },
-                ActionReq::Init(init_req) => {
-                    let d = clients.get_mut(&endpoint).unwrap();
-                    d.faction = Some(init_req.faction);
-                    d.id = Some(init_req.name);
-
-                    let response = match (init_req.faction) {
-                        Faction::Sanctum => InitStateResponse {
-                            my_state: Some(create_sanctum_state_response(
-                                true, &instances, &sanc_deck, &sanc_hand,
-                            )),
-                            other_state: Some(create_thief_state_response(
-                                false,
-                                &instances,
-                                &thief_deck,
-                                &thief_hand,
-                            )),
-                            card_set: cards.clone(),
-                            turn: Faction::Sanctum,
-                        },
-                        Faction::Thief => InitStateResponse {
-                            my_state: Some(create_thief_state_response(
-                                true,
-                                &instances,
-                                &thief_deck,
-                                &thief_hand,
-                            )),
-                            other_state: Some(create_sanctum_state_response(
-                                false, &instances, &sanc_deck, &sanc_hand,
-                            )),
-                            card_set: cards.clone(),
-                            turn: Faction::Sanctum,
+                ActionReq::ListTables => {
+                    send(
+                        &node,
+                        endpoint,
+                        &Response::Tables {
+                            tables: lobby.table_infos(),
                         },
+                    );
+                }
+                ActionReq::CreateTable(name) => {
+                    let seed = forced_seed.unwrap_or_else(rand::random);
+                    match lobby.create_table(&name, seed) {
+                        Ok(()) => {
+                            println!(
+                                "Table {} seed: {} (replay with --seed {})",
+                                name, seed, seed
+                            );
+                            persist(snapshot.as_deref(), &lobby);
+                            send(
+                                &node,
+                                endpoint,
+                                &Response::Tables {
+                                    tables: lobby.table_infos(),
+                                },
+                            );
+                        }
+                        Err(err) => send(
+                            &node,
+                            endpoint,
+                            &Response::LobbyRejected {
+                                reason: err.to_string(),
+                            },
+                        ),
+                    }
+                }
+                ActionReq::Init(init_req) => {
+                    let player = clients.get_mut(&endpoint).unwrap();
+                    if player.table.is_some() {
+                        send(
+                            &node,
+                            endpoint,
+                            &Response::InitRejected {
+                                reason: "already seated at a table".to_string(),
+                            },
+                        );
+                        return;
+                    }
+                    let (token, started) = match lobby.join(
+                        &init_req.table,
+                        init_req.faction,
+                        &init_req.name,
+                        &init_req.deck,
+                    ) {
+                        Ok(joined) => joined,
+                        Err(err) => {
+                            println!("Rejected {} at {}: {}", init_req.name, init_req.table, err);
+                            send(
+                                &node,
+                                endpoint,
+                                &Response::InitRejected {
+                                    reason: err.to_string(),
+                                },
+                            );
+                            return;
+                        }
                     };
-                    let bytes = bincode::serialize(&Response::Initial(response)).unwrap();
-                    node.network().send(endpoint, &bytes);
+                    player.faction = Some(init_req.faction);
+                    player.id = Some(init_req.name);
+                    player.table = Some(init_req.table.clone());
+                    send(&node, endpoint, &Response::Joined { token });
+                    persist(snapshot.as_deref(), &lobby);
+
+                    // Start as soon as both seats are taken.
+                    if started {
+                        let table = &lobby.tables[&init_req.table];
+                        let game = table.game.as_ref().unwrap();
+                        let header = replay_header(
+                            &init_req.table,
+                            game,
+                            &table.sanctum.as_ref().unwrap().deck,
+                            &table.thief.as_ref().unwrap().deck,
+                        );
+                        let replay = match GameLog::create(&replays, &header) {
+                            Ok(log) => {
+                                println!("Recording {} to {}", init_req.table, log.path.display());
+                                let path = log.path.clone();
+                                logs.insert(init_req.table.clone(), log);
+                                Some(path)
+                            }
+                            Err(err) => {
+                                println!("Not recording {}: {}", init_req.table, err);
+                                None
+                            }
+                        };
+                        for (client, player) in clients.iter() {
+                            if player.table.as_ref() != Some(&init_req.table) {
+                                continue;
+                            }
+                            match player.faction {
+                                Some(faction) => {
+                                    let response = game.init_state_response(faction);
+                                    send(&node, *client, &Response::Initial(response));
+                                }
+                                None => spectators.send_state(&node, *client, game),
+                            }
+                        }
+                        lobby.tables.get_mut(&init_req.table).unwrap().replay = replay;
+                        persist(snapshot.as_deref(), &lobby);
+                    }
+                }
+                ActionReq::Resume(token) => {
+                    let session = match lobby.resume(&token) {
+                        Ok(session) => session,
+                        Err(err) => {
+                            send(
+                                &node,
+                                endpoint,
+                                &Response::InitRejected {
+                                    reason: err.to_string(),
+                                },
+                            );
+                            return;
+                        }
+                    };
+                    // A stale connection that never noticed it dropped loses the seat.
+                    for player in clients.values_mut() {
+                        if player.table.as_ref() == Some(&session.table)
+                            && player.faction == Some(session.faction)
+                        {
+                            player.table = None;
+                            player.faction = None;
+                        }
+                    }
+                    let player = clients.get_mut(&endpoint).unwrap();
+                    player.faction = Some(session.faction);
+                    player.id = Some(session.player);
+                    player.table = Some(session.table.clone());
+                    println!("Client ({}) resumed at {}", endpoint.addr(), session.table);
+
+                    if let Some(game) = &lobby.tables[&session.table].game {
+                        let response = game.init_state_response(session.faction);
+                        send(&node, endpoint, &Response::Initial(response));
+                    }
+                    broadcast(
+                        &node,
+                        &clients,
+                        &mut spectators,
+                        &session.table,
+                        &Update::Public(Response::PlayerConnection {
+                            faction: session.faction,
+                            connected: true,
+                        }),
+                    );
+                }
+                ActionReq::Spectate(table) => {
+                    let player = clients.get_mut(&endpoint).unwrap();
+                    if player.table.is_some() {
+                        send(
+                            &node,
+                            endpoint,
+                            &Response::InitRejected {
+                                reason: "already seated at a table".to_string(),
+                            },
+                        );
+                        return;
+                    }
+                    let Some(watched) = lobby.tables.get(&table) else {
+                        send(
+                            &node,
+                            endpoint,
+                            &Response::InitRejected {
+                                reason: LobbyError::UnknownTable(table).to_string(),
+                            },
+                        );
+                        return;
+                    };
+                    player.table = Some(table.clone());
+                    println!("Client ({}) is watching {}", endpoint.addr(), table);
+                    // Otherwise they get the state along with the players when the game starts.
+                    if let Some(game) = &watched.game {
+                        spectators.send_state(&node, endpoint, game);
+                    }
+                }
+                action => {
+                    let player = &clients[&endpoint];
+                    if player.table.is_some() && player.faction.is_none() {
+                        reject(ActionError::Spectating);
+                        return;
+                    }
+                    let (Some(faction), Some(table_name)) = (player.faction, &player.table) else {
+                        println!(
+                            "Client ({}) sent {:?} before joining",
+                            endpoint.addr(),
+                            action
+                        );
+                        reject(ActionError::NotJoined);
+                        return;
+                    };
+                    let Some(game) = lobby
+                        .tables
+                        .get_mut(table_name)
+                        .and_then(|table| table.game.as_mut())
+                    else {
+                        println!(
+                            "Client ({}) sent {:?} before the game started",
+                            endpoint.addr(),
+                            action
+                        );
+                        reject(ActionError::GameNotStarted);
+                        return;
+                    };
+                    match game.handle_action(faction, &action) {
+                        Ok(updates) => {
+                            for update in updates.iter() {
+                                broadcast(&node, &clients, &mut spectators, table_name, update);
+                            }
+                            let event = GameEvent::Action {
+                                faction,
+                                action,
+                                updates,
+                            };
+                            record(&mut logs, table_name, &event);
+                            persist(snapshot.as_deref(), &lobby);
+                        }
+                        Err(err) => {
+                            println!("Rejected {:?} from {}: {}", action, faction, err);
+                            reject(err);
+                        }
+                    }
                 }
             }
         }
-        NetEvent::Disconnected(endpoint) => {
+        NodeEvent::Network(NetEvent::Disconnected(endpoint)) => {
             // Only connection oriented protocols will generate this event
-            clients.remove(&endpoint);
+            if let Some(player) = clients.remove(&endpoint)
+                && let (Some(table), Some(faction)) = (player.table, player.faction)
+            {
+                // The seat is held for a while in case they come back.
+                lobby.leave(&table, faction);
+                node.signals()
+                    .send_with_timer(Signal::ExpireSeats, RECONNECT_GRACE);
+                broadcast(
+                    &node,
+                    &clients,
+                    &mut spectators,
+                    &table,
+                    &Update::Public(Response::PlayerConnection {
+                        faction,
+                        connected: false,
+                    }),
+                );
+            }
             println!("Client ({}) disconnected", endpoint.addr());
         }
     });
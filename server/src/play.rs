@@ -0,0 +1,154 @@
+use common::card::{CardType, Faction};
+use common::{InstanceId, Response};
+
+use crate::game_state::{ActionError, GameState, Update};
+use crate::instances::InstantiateLocation;
+
+impl GameState {
+    /// Plays an Event for the Thief or an Operation for the Sanctum. The card
+    /// is revealed and discarded, then its effects resolve.
+    pub(crate) fn play_card(
+        &mut self,
+        faction: Faction,
+        instance_id: InstanceId,
+    ) -> Result<Vec<Update>, ActionError> {
+        let (_, hand) = self.deck_and_hand_mut(faction);
+        let Some(index) = hand.iter().position(|&id| id == instance_id) else {
+            return Err(ActionError::NotInHand);
+        };
+        let card = self.card_data(instance_id).clone();
+        if !matches!(
+            (faction, &card.data),
+            (Faction::Sanctum, CardType::Operation { .. })
+                | (Faction::Thief, CardType::Event { .. })
+        ) {
+            return Err(ActionError::CannotPlay);
+        }
+        self.pay_mana(faction, card.data.cost().unwrap_or(0))?;
+
+        self.deck_and_hand_mut(faction).1.remove(index);
+        let played = self
+            .instances
+            .move_to(instance_id, InstantiateLocation::Trash, &mut self.rng);
+        match faction {
+            Faction::Sanctum => self.sanctum.discard.push(played),
+            Faction::Thief => self.thief.discard.push(played),
+        }
+
+        let card_state = self.instances.create_card_state(played, true);
+        let mut updates = vec![Update::Private {
+            owner: faction,
+            owner_view: Response::Played {
+                faction,
+                card: card_state.clone(),
+                from_hand: Some(instance_id),
+            },
+            other_view: Response::Played {
+                faction,
+                card: card_state,
+                from_hand: None,
+            },
+        }];
+        updates.extend(self.resolve_effects(faction, played, &card.effects));
+        Ok(updates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::ActionReq;
+    use common::card::{CardState, Effect, Mana, WardSubType};
+    use common::rules::{SANCTUM_STAMINA, STARTING_MANA, THIEF_STAMINA};
+
+    use super::*;
+    use crate::testing::{card, game, in_hand, turn};
+
+    fn play_game() -> GameState {
+        let mut event = card("event", Faction::Thief, CardType::Event { cost: Mana(1) });
+        event.effects = vec![Effect::GainMana(3)];
+        let mut operation = card(
+            "operation",
+            Faction::Sanctum,
+            CardType::Operation {
+                subtype: None,
+                cost: Mana(0),
+            },
+        );
+        operation.effects = vec![Effect::Draw(2)];
+        let ward = card(
+            "ward",
+            Faction::Sanctum,
+            CardType::Ward {
+                subtype: WardSubType::Glyph,
+                cost: Mana(0),
+                strength: 1,
+                subroutines: vec![Effect::EndInfiltration],
+            },
+        );
+        game(vec![operation, ward, event])
+    }
+
+    #[test]
+    fn played_cards_are_discarded_and_resolve() {
+        let mut game = play_game();
+        let operation = in_hand(&mut game, Faction::Sanctum, "operation");
+        let hand = game.sanctum.hand.len();
+        let updates = game
+            .handle_action(Faction::Sanctum, &ActionReq::PlayCard(operation))
+            .unwrap();
+        // Out goes the operation, in come the two it draws.
+        assert_eq!(game.sanctum.hand.len(), hand + 1);
+        assert!(!game.sanctum.hand.contains(&operation));
+        assert_eq!(game.sanctum.discard.len(), 1);
+        let Update::Private {
+            owner_view: Response::Played { from_hand, .. },
+            other_view:
+                Response::Played {
+                    card: CardState::Revealed(..),
+                    from_hand: None,
+                    ..
+                },
+            ..
+        } = &updates[0]
+        else {
+            panic!("not a private play: {:?}", updates[0]);
+        };
+        assert_eq!(*from_hand, Some(operation));
+
+        turn(&mut game, Faction::Thief);
+        let event = in_hand(&mut game, Faction::Thief, "event");
+        game.handle_action(Faction::Thief, &ActionReq::PlayCard(event))
+            .unwrap();
+        assert_eq!(game.thief.stats.mana_pool, STARTING_MANA - 1 + 3);
+        assert_eq!(game.thief.stats.stamina, THIEF_STAMINA - 1);
+    }
+
+    #[test]
+    fn only_affordable_events_and_operations_are_played() {
+        let mut game = play_game();
+        let ward = in_hand(&mut game, Faction::Sanctum, "ward");
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::PlayCard(ward))
+                .err(),
+            Some(ActionError::CannotPlay)
+        );
+        let in_deck = *game.sanctum.deck.last().unwrap();
+        assert_eq!(
+            game.handle_action(Faction::Sanctum, &ActionReq::PlayCard(in_deck))
+                .err(),
+            Some(ActionError::NotInHand)
+        );
+        assert_eq!(game.sanctum.stats.stamina, SANCTUM_STAMINA);
+
+        turn(&mut game, Faction::Thief);
+        let event = in_hand(&mut game, Faction::Thief, "event");
+        game.thief.stats.mana_pool = 0;
+        assert_eq!(
+            game.handle_action(Faction::Thief, &ActionReq::PlayCard(event))
+                .err(),
+            Some(ActionError::NotEnoughMana)
+        );
+        assert!(game.thief.hand.contains(&event));
+        assert!(game.thief.discard.is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use common::deck::DeckList;
+use common::replay::{GameEvent, REPLAY_VERSION, ReplayFile, ReplayHeader};
+use common::{GameOverReason, Update};
+use serde::Serialize;
+
+use crate::game_state::{GameState, other_faction};
+
+pub const REPLAYS_DIRECTORY: &str = "replays";
+
+/// Table names come from clients, keep them from reaching outside the directory.
+fn sanitize(table: &str) -> String {
+    table
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '-' {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+/// Appends a game's events to its replay file as they happen, so a crashed
+/// server still leaves everything up to the crash on disk.
+pub struct GameLog {
+    file: File,
+    pub path: PathBuf,
+}
+impl GameLog {
+    /// Starts a new file for the game, never overwriting an earlier recording.
+    /// Replaying a seed or reusing a table name gets a file of its own.
+    pub fn create(directory: &Path, header: &ReplayHeader) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let stem = format!("{}-{}-{}", sanitize(&header.table), header.seed, started);
+        let mut attempt = 1;
+        let (file, path) = loop {
+            let path = match attempt {
+                1 => directory.join(format!("{}.jsonl", stem)),
+                n => directory.join(format!("{}-{}.jsonl", stem, n)),
+            };
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break (file, path),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+                Err(err) => return Err(err),
+            }
+        };
+        let mut log = Self { file, path };
+        log.write_line(header)?;
+        Ok(log)
+    }
+
+    /// Carries on recording a game that was started before the server restarted.
+    pub fn reopen(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn append(&mut self, event: &GameEvent) -> io::Result<()> {
+        self.write_line(event)
+    }
+
+    fn write_line(&mut self, value: &impl Serialize) -> io::Result<()> {
+        let line = serde_json::to_string(value)?;
+        writeln!(self.file, "{}", line)
+    }
+}
+
+pub fn replay_header(
+    table: &str,
+    game: &GameState,
+    sanctum_deck: &DeckList,
+    thief_deck: &DeckList,
+) -> ReplayHeader {
+    ReplayHeader {
+        version: REPLAY_VERSION,
+        table: table.to_string(),
+        seed: game.seed,
+        sanctum_deck: sanctum_deck.clone(),
+        thief_deck: thief_deck.clone(),
+        initial: game.spectator_state_response(true),
+    }
+}
+
+/// A replay whose game step `step` didn't reproduce what was recorded, most
+/// likely because the rules changed since it was played.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Desync {
+    pub step: usize,
+}
+
+/// Rebuilds a recorded game by feeding its events back through the rules,
+/// one step at a time.
+pub struct Replayer {
+    pub file: ReplayFile,
+    pub game: GameState,
+    pub step: usize,
+}
+impl Replayer {
+    pub fn new(file: ReplayFile) -> Self {
+        let game = Self::initial_game(&file);
+        Self {
+            file,
+            game,
+            step: 0,
+        }
+    }
+
+    fn initial_game(file: &ReplayFile) -> GameState {
+        // The card set is part of the recording, edits to cards.json don't change old games.
+        GameState::new(
+            file.header.initial.card_set.clone(),
+            &file.header.sanctum_deck,
+            &file.header.thief_deck,
+            file.header.seed,
+        )
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.step >= self.file.events.len()
+    }
+
+    /// Applies the next event and returns the updates it produced, `None` once
+    /// every event has been replayed.
+    pub fn step(&mut self) -> Option<Result<Vec<Update>, Desync>> {
+        let event = self.file.events.get(self.step)?;
+        let updates = match event {
+            GameEvent::Action {
+                faction, action, ..
+            } => self
+                .game
+                .handle_action(*faction, action)
+                .unwrap_or_default(),
+            GameEvent::Forfeit { loser, .. } => {
+                vec![
+                    self.game
+                        .end_game(other_faction(*loser), GameOverReason::Forfeit),
+                ]
+            }
+        };
+        let desync = Desync { step: self.step };
+        self.step += 1;
+        // Comparing the encoded form is enough, responses don't implement PartialEq.
+        let encode = |updates: &[Update]| serde_json::to_string(updates).unwrap();
+        if encode(&updates) != encode(event.updates()) {
+            return Some(Err(desync));
+        }
+        Some(Ok(updates))
+    }
+
+    /// Rebuilds the game from the start up to just before event `step`.
+    pub fn seek(&mut self, step: usize) -> Result<(), Desync> {
+        if step < self.step {
+            self.game = Self::initial_game(&self.file);
+            self.step = 0;
+        }
+        while self.step < step.min(self.file.events.len()) {
+            self.step().unwrap()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use common::versioned::{VersionedError, parse_versioned};
+use serde::{Deserialize, Serialize};
+
+use crate::lobby::{Lobby, Table};
+
+// Bumped whenever the server state changes shape, old snapshots are refused rather than misread.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// Every table on the server, games included down to the RNG, so a restarted
+/// server carries on exactly where it stopped.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub tables: BTreeMap<String, Table>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    Io(String),
+    Json(String),
+    UnsupportedVersion(u32),
+}
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(message) => write!(f, "{}", message),
+            SnapshotError::Json(message) => write!(f, "{}", message),
+            SnapshotError::UnsupportedVersion(version) => write!(
+                f,
+                "snapshot version {} isn't supported, expected {}",
+                version, SNAPSHOT_VERSION
+            ),
+        }
+    }
+}
+impl std::error::Error for SnapshotError {}
+
+/// Writes every table of `lobby` to `path`. The file is replaced in one go, so a
+/// crash while saving leaves the previous snapshot intact.
+pub fn save_snapshot(path: &Path, lobby: &Lobby) -> Result<(), SnapshotError> {
+    #[derive(Serialize)]
+    struct SnapshotRef<'a> {
+        version: u32,
+        tables: &'a BTreeMap<String, Table>,
+    }
+    let json = serde_json::to_string(&SnapshotRef {
+        version: SNAPSHOT_VERSION,
+        tables: &lobby.tables,
+    })
+    .map_err(|err| SnapshotError::Json(err.to_string()))?;
+    let partial = path.with_extension("partial");
+    fs::write(&partial, json).map_err(|err| SnapshotError::Io(err.to_string()))?;
+    fs::rename(&partial, path).map_err(|err| SnapshotError::Io(err.to_string()))
+}
+
+pub fn load_snapshot(path: &Path) -> Result<Snapshot, SnapshotError> {
+    let contents = fs::read_to_string(path).map_err(|err| SnapshotError::Io(err.to_string()))?;
+    parse_versioned(&contents, SNAPSHOT_VERSION).map_err(|err| match err {
+        VersionedError::Json(err) => SnapshotError::Json(err.to_string()),
+        VersionedError::UnsupportedVersion(version) => SnapshotError::UnsupportedVersion(version),
+    })
+}
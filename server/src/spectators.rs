@@ -0,0 +1,56 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use common::Response;
+
+/// What spectators get to see, set once for the whole server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpectatorVisibility {
+    /// Only what both players can see, as it happens.
+    Public,
+    /// Everything, hands included, held back for a while so it can be streamed.
+    Delayed(Duration),
+}
+impl SpectatorVisibility {
+    /// Reads `public` or `delayed:<seconds>`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.split_once(':') {
+            None if value == "public" => Some(SpectatorVisibility::Public),
+            Some(("delayed", seconds)) => seconds
+                .parse()
+                .ok()
+                .map(|seconds| SpectatorVisibility::Delayed(Duration::from_secs(seconds))),
+            _ => None,
+        }
+    }
+    pub fn is_omniscient(&self) -> bool {
+        matches!(self, SpectatorVisibility::Delayed(_))
+    }
+}
+
+/// Responses waiting to be sent to `R` once the spectator delay has passed.
+pub struct DelayedFeed<R> {
+    delay: Duration,
+    queue: VecDeque<(Instant, R, Response)>,
+}
+impl<R> DelayedFeed<R> {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            queue: VecDeque::new(),
+        }
+    }
+    pub fn push(&mut self, now: Instant, recipient: R, response: Response) {
+        self.queue
+            .push_back((now + self.delay, recipient, response));
+    }
+    /// Everything that's due, in the order it was pushed.
+    pub fn pop_due(&mut self, now: Instant) -> Vec<(R, Response)> {
+        let mut due = vec![];
+        while self.queue.front().is_some_and(|(at, _, _)| *at <= now) {
+            let (_, recipient, response) = self.queue.pop_front().unwrap();
+            due.push((recipient, response));
+        }
+        due
+    }
+}
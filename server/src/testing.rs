@@ -0,0 +1,61 @@
+//! Card sets and games for the rules tests, as small as each test allows.
+
+use std::collections::HashMap;
+
+use common::InstanceId;
+use common::card::{CardData, CardType, Faction};
+use common::deck::DeckList;
+use common::rules::{SANCTUM_STAMINA, THIEF_STAMINA};
+
+use crate::game_state::GameState;
+use crate::instances::InstantiateLocation;
+
+pub fn card(id: &str, faction: Faction, data: CardType) -> CardData {
+    CardData {
+        id: id.to_string(),
+        title: id.to_string(),
+        faction,
+        text: String::new(),
+        image_file: String::new(),
+        data,
+        effects: vec![],
+        counter_abilities: vec![],
+    }
+}
+
+/// A game with `cards` as its card set, where each side's deck is ten copies of
+/// the first of its cards. Everything else is only put in play by the test.
+pub fn game(cards: Vec<CardData>) -> GameState {
+    let deck = |faction| {
+        let card = cards.iter().find(|card| card.faction == faction).unwrap();
+        DeckList::from([(card.id.clone(), 10)])
+    };
+    let (sanctum_deck, thief_deck) = (deck(Faction::Sanctum), deck(Faction::Thief));
+    let cards = cards
+        .into_iter()
+        .map(|card| (card.id.clone(), card))
+        .collect::<HashMap<_, _>>();
+    GameState::new(cards, &sanctum_deck, &thief_deck, 1)
+}
+
+/// A fresh copy of `card` at `location`, which the test still has to put in a zone.
+pub fn instance(game: &mut GameState, card: &str, location: InstantiateLocation) -> InstanceId {
+    game.instances
+        .create_instance(&card.to_string(), location, &mut game.rng)
+}
+
+/// A fresh copy of `card` in `faction`'s hand.
+pub fn in_hand(game: &mut GameState, faction: Faction, card: &str) -> InstanceId {
+    let instance_id = instance(game, card, InstantiateLocation::Hand);
+    game.deck_and_hand_mut(faction).1.push(instance_id);
+    instance_id
+}
+
+/// Starts `faction`'s turn with full stamina, skipping the other side's.
+pub fn turn(game: &mut GameState, faction: Faction) {
+    game.turn = faction;
+    game.stats_mut(faction).stamina = match faction {
+        Faction::Sanctum => SANCTUM_STAMINA,
+        Faction::Thief => THIEF_STAMINA,
+    };
+}
@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use bot::strategy::Strategy;
+use bot::view::BotView;
+use common::card::{CardData, Faction};
+use common::deck::DeckList;
+use common::replay::{GameEvent, ReplayFile};
+use common::{ActionReq, CardId, GameOverReason, Response};
+use server::game_state::GameState;
+use server::replay::replay_header;
+
+/// A game still going after this many accepted actions is given up on, most
+/// likely both strategies are just gaining mana.
+pub const MAX_ACTIONS: u32 = 5000;
+
+/// How often each card came up over one or more games.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CardStats {
+    pub drawn: u32,
+    pub played: u32,
+    pub accessed: u32,
+    pub scored: u32,
+}
+
+pub struct GameReport {
+    pub seed: u64,
+    /// `None` when the game hit `MAX_ACTIONS` or neither side had a move left.
+    pub outcome: Option<(Faction, GameOverReason)>,
+    pub turns: u32,
+    pub actions: u32,
+    pub cards: HashMap<CardId, CardStats>,
+    /// The game as the server would have recorded it.
+    pub replay: ReplayFile,
+}
+
+/// Plays one game between two strategies straight on the rules engine. Each
+/// strategy only sees what the server would send its seat.
+pub fn simulate_game(
+    cards: &HashMap<CardId, CardData>,
+    sanctum_deck: &DeckList,
+    thief_deck: &DeckList,
+    seed: u64,
+    sanctum: &mut dyn Strategy,
+    thief: &mut dyn Strategy,
+) -> GameReport {
+    let mut game = GameState::new(cards.clone(), sanctum_deck, thief_deck, seed);
+    let mut sanctum_view = BotView::new(&game.init_state_response(Faction::Sanctum));
+    let mut thief_view = BotView::new(&game.init_state_response(Faction::Thief));
+    let mut report = GameReport {
+        seed,
+        outcome: None,
+        turns: 0,
+        actions: 0,
+        cards: HashMap::new(),
+        replay: ReplayFile {
+            header: replay_header("simulation", &game, sanctum_deck, thief_deck),
+            events: vec![],
+        },
+    };
+    // Opening hands count as draws too.
+    for view in [&sanctum_view, &thief_view] {
+        for card in view.hand.iter() {
+            count_card(
+                &mut report.cards,
+                &Response::DrawCard {
+                    faction: view.faction,
+                    card: Some(card.clone()),
+                },
+            );
+        }
+    }
+    // Turned down since the game last changed, as in the networked bot.
+    let mut refused: Vec<ActionReq> = vec![];
+
+    while !game.is_over() && report.actions < MAX_ACTIONS {
+        let faction = game.priority();
+        let (view, strategy): (&BotView, &mut dyn Strategy) = match faction {
+            Faction::Sanctum => (&sanctum_view, &mut *sanctum),
+            Faction::Thief => (&thief_view, &mut *thief),
+        };
+        let legal: Vec<ActionReq> = view
+            .legal_actions()
+            .into_iter()
+            .filter(|action| !refused.contains(action))
+            .collect();
+        if legal.is_empty() {
+            break;
+        }
+        let action = strategy.choose(view, &legal);
+        match game.handle_action(faction, &action) {
+            Ok(updates) => {
+                report.actions += 1;
+                refused.clear();
+                for update in updates.iter() {
+                    sanctum_view.apply(update.view_for(Faction::Sanctum));
+                    thief_view.apply(update.view_for(Faction::Thief));
+                    // The owner's view is the one with the card in it.
+                    count_card(&mut report.cards, update.spectator_view(true));
+                }
+                report.replay.events.push(GameEvent::Action {
+                    faction,
+                    action,
+                    updates,
+                });
+            }
+            Err(_) => refused.push(action),
+        }
+    }
+    report.outcome = game.outcome;
+    report.turns = game.turn_number;
+    report
+}
+
+fn count_card(cards: &mut HashMap<CardId, CardStats>, response: &Response) {
+    let (card, field): (_, fn(&mut CardStats) -> &mut u32) = match response {
+        Response::DrawCard {
+            card: Some(card), ..
+        } => (card, |stats| &mut stats.drawn),
+        Response::Played { card, .. } => (card, |stats| &mut stats.played),
+        Response::Access { card } => (card, |stats| &mut stats.accessed),
+        Response::Scored { card, .. } => (card, |stats| &mut stats.scored),
+        _ => return,
+    };
+    if let Some(card_id) = card.get_card_id() {
+        *field(cards.entry(card_id).or_default()) += 1;
+    }
+}
+
+/// Totals over a batch of games.
+#[derive(Default)]
+pub struct Summary {
+    pub games: u32,
+    pub sanctum_wins: u32,
+    pub thief_wins: u32,
+    pub unfinished: u32,
+    pub reasons: BTreeMap<String, u32>,
+    pub turns: u64,
+    pub actions: u64,
+    pub cards: BTreeMap<CardId, CardStats>,
+}
+impl Summary {
+    pub fn add(&mut self, report: &GameReport) {
+        self.games += 1;
+        match report.outcome {
+            Some((Faction::Sanctum, _)) => self.sanctum_wins += 1,
+            Some((Faction::Thief, _)) => self.thief_wins += 1,
+            None => self.unfinished += 1,
+        }
+        if let Some((_, reason)) = report.outcome {
+            *self.reasons.entry(format!("{:?}", reason)).or_default() += 1;
+        }
+        self.turns += report.turns as u64;
+        self.actions += report.actions as u64;
+        for (card_id, stats) in report.cards.iter() {
+            let total = self.cards.entry(card_id.clone()).or_default();
+            total.drawn += stats.drawn;
+            total.played += stats.played;
+            total.accessed += stats.accessed;
+            total.scored += stats.scored;
+        }
+    }
+}
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let games = self.games.max(1) as f64;
+        let percent = |count: u32| 100.0 * count as f64 / games;
+        writeln!(f, "{} games", self.games)?;
+        writeln!(
+            f,
+            "Sanctum wins {} ({:.1}%), Thief wins {} ({:.1}%), unfinished {}",
+            self.sanctum_wins,
+            percent(self.sanctum_wins),
+            self.thief_wins,
+            percent(self.thief_wins),
+            self.unfinished
+        )?;
+        for (reason, count) in self.reasons.iter() {
+            writeln!(f, "  {}: {}", reason, count)?;
+        }
+        writeln!(
+            f,
+            "Average length: {:.1} turns, {:.1} actions",
+            self.turns as f64 / games,
+            self.actions as f64 / games
+        )?;
+        writeln!(
+            f,
+            "{:<12} {:>8} {:>8} {:>8} {:>8}",
+            "card", "drawn", "played", "accessed", "scored"
+        )?;
+        for (card_id, stats) in self.cards.iter() {
+            writeln!(
+                f,
+                "{:<12} {:>8} {:>8} {:>8} {:>8}",
+                card_id, stats.drawn, stats.played, stats.accessed, stats.scored
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use bot::strategy::strategy_by_name;
+use common::args::arg_value;
+use common::card::{Faction, load_cards_from_json};
+use common::deck::DeckChoice;
+use server::deck::{resolve_deck, validate_deck};
+use server::replay::Replayer;
+use simulator::{Summary, simulate_game};
+
+/// `simulate [--games <n>] [--seed <n>] [--sanctum random|greedy] [--thief random|greedy]
+/// [--sanctum-deck <name>] [--thief-deck <name>] [--verify]`
+///
+/// Game `i` is played with seed `seed + i`, so any single game can be looked at
+/// again with the same flags. `--verify` also replays every game from its
+/// recording and reports the ones that don't come out the same.
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let number = |flag: &str, default: u64| {
+        arg_value(&args, flag).map_or(default, |value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("{} must be a number", flag))
+        })
+    };
+    let games = number("--games", 1000);
+    let seed = number("--seed", 0);
+    let sanctum_strategy = arg_value(&args, "--sanctum").unwrap_or("greedy");
+    let thief_strategy = arg_value(&args, "--thief").unwrap_or("greedy");
+    let verify = args.iter().any(|arg| arg == "--verify");
+
+    let cards = load_cards_from_json("cards.json").unwrap_or_else(|errors| {
+        eprint!("cards.json has problems:\n{}", errors);
+        std::process::exit(1);
+    });
+    let deck = |faction: Faction, flag: &str, default: &str| {
+        let name = arg_value(&args, flag).unwrap_or(default);
+        let deck = resolve_deck(&DeckChoice::Named(name.to_string()))
+            .unwrap_or_else(|err| panic!("{}: {}", name, err));
+        validate_deck(faction, &deck, &cards).unwrap_or_else(|err| panic!("{}: {}", name, err));
+        deck
+    };
+    let sanctum_deck = deck(Faction::Sanctum, "--sanctum-deck", "sanctum_starter");
+    let thief_deck = deck(Faction::Thief, "--thief-deck", "thief_starter");
+
+    let mut summary = Summary::default();
+    let mut desyncs = 0;
+    for game_seed in seed..seed + games {
+        // The strategies get seeds of their own so a random bot doesn't mirror the deal.
+        let mut sanctum = strategy_by_name(sanctum_strategy, game_seed.wrapping_mul(2))
+            .expect("--sanctum must be random or greedy");
+        let mut thief = strategy_by_name(thief_strategy, game_seed.wrapping_mul(2) + 1)
+            .expect("--thief must be random or greedy");
+        let report = simulate_game(
+            &cards,
+            &sanctum_deck,
+            &thief_deck,
+            game_seed,
+            sanctum.as_mut(),
+            thief.as_mut(),
+        );
+        if report.outcome.is_none() {
+            println!("Game with seed {} didn't finish", report.seed);
+        }
+        summary.add(&report);
+        if verify {
+            let mut replayer = Replayer::new(report.replay);
+            if let Err(desync) = replayer.seek(replayer.file.events.len()) {
+                println!(
+                    "Replay of the game with seed {} desynced at step {}",
+                    report.seed, desync.step
+                );
+                desyncs += 1;
+            }
+        }
+    }
+    println!(
+        "Sanctum ({}) against Thief ({})",
+        sanctum_strategy, thief_strategy
+    );
+    print!("{}", summary);
+    if verify {
+        println!("{} of {} replays desynced", desyncs, summary.games);
+        if desyncs > 0 {
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use bot::strategy::strategy_by_name;
+use common::card::read_cards;
+use common::deck::load_deck_from_json;
+use common::replay::load_replay;
+use server::replay::{GameLog, Replayer};
+use simulator::simulate_game;
+
+fn repository_file(path: &str) -> String {
+    format!("{}/../{}", env!("CARGO_MANIFEST_DIR"), path)
+}
+
+#[test]
+fn simulated_game_replays_without_desync() {
+    let (cards, _) = read_cards(&repository_file("cards.json")).unwrap();
+    let cards = cards.into_iter().collect();
+    let sanctum_deck = load_deck_from_json(&repository_file("decks/sanctum_starter.json")).unwrap();
+    let thief_deck = load_deck_from_json(&repository_file("decks/thief_starter.json")).unwrap();
+    let mut sanctum = strategy_by_name("random", 6).unwrap();
+    let mut thief = strategy_by_name("greedy", 7).unwrap();
+    let report = simulate_game(
+        &cards,
+        &sanctum_deck,
+        &thief_deck,
+        3,
+        sanctum.as_mut(),
+        thief.as_mut(),
+    );
+    assert!(!report.replay.events.is_empty());
+
+    let directory: PathBuf =
+        std::env::temp_dir().join(format!("replay-test-{}", std::process::id()));
+    let mut log = GameLog::create(&directory, &report.replay.header).unwrap();
+    for event in report.replay.events.iter() {
+        log.append(event).unwrap();
+    }
+    let loaded = load_replay(log.path.to_str().unwrap()).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(loaded.events.len(), report.replay.events.len());
+
+    let mut replayer = Replayer::new(loaded);
+    while let Some(step) = replayer.step() {
+        assert!(step.is_ok(), "{:?}", step.err());
+    }
+    assert!(replayer.is_finished());
+    assert_eq!(replayer.game.outcome, report.outcome);
+}